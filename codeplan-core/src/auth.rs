@@ -0,0 +1,127 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use chrono::prelude::*;
+use chrono::Duration;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Error, Result};
+use crate::files;
+
+/// Default credentials file, in the config directory next to `config.toml`.
+pub const CREDENTIALS_FILE: &str = "client.json";
+/// Default token cache, in the server's data directory.
+pub const TOKEN_FILE: &str = "token.json";
+
+/// Tokens without an `expires_in` are assumed to live this long.
+const DEFAULT_TOKEN_LIFETIME: i64 = 3600;
+/// Refresh a little before the real expiry so in-flight requests don't race it.
+const EXPIRY_MARGIN: i64 = 30;
+
+/// OAuth client credentials, as stored in `client.json`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Credentials {
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+impl Credentials {
+    /// Reads `client.json`, returning `None` when it is missing or left blank.
+    pub fn load(path: &Path) -> Result<Option<Credentials>> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let credentials: Credentials = serde_json::from_str(&content)?;
+        if credentials.client_id.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(credentials))
+    }
+}
+
+/// Bearer token as persisted in the token cache.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Token {
+    pub access_token: String,
+    pub expires_at: DateTime<Utc>,
+    pub token_url: String,
+}
+
+impl Token {
+    pub fn is_expired(&self) -> bool {
+        Utc::now() + Duration::seconds(EXPIRY_MARGIN) >= self.expires_at
+    }
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    #[serde(default)]
+    expires_in: Option<i64>,
+}
+
+/// Client-credentials flow with a file-backed token cache.
+pub struct Auth {
+    credentials: Credentials,
+    token_url: String,
+    cache_path: PathBuf,
+    token: Mutex<Option<Token>>,
+}
+
+impl Auth {
+    pub fn new(credentials: Credentials, token_url: &str, cache_path: &Path) -> Auth {
+        let token = read_token(cache_path).filter(|token| token.token_url == token_url);
+        Auth {
+            credentials,
+            token_url: token_url.to_owned(),
+            cache_path: cache_path.to_owned(),
+            token: Mutex::new(token),
+        }
+    }
+
+    /// Cached access token, fetching a new one if there is none or it has expired.
+    pub async fn token(&self, http: &reqwest::Client) -> Result<String> {
+        let cached = self.token.lock().unwrap().clone();
+        match cached {
+            Some(token) if !token.is_expired() => Ok(token.access_token),
+            _ => self.refresh(http).await,
+        }
+    }
+
+    /// Exchanges the client credentials for a new token and persists it.
+    pub async fn refresh(&self, http: &reqwest::Client) -> Result<String> {
+        let params = [
+            ("grant_type", "client_credentials"),
+            ("client_id", self.credentials.client_id.as_str()),
+            ("client_secret", self.credentials.client_secret.as_str()),
+        ];
+        let res = http.post(&self.token_url).form(&params).send().await?;
+        if !res.status().is_success() {
+            return Err(Error::AuthError {
+                url: self.token_url.clone(),
+                status: res.status(),
+            });
+        }
+        let body: TokenResponse = res.json().await?;
+        let token = Token {
+            access_token: body.access_token,
+            expires_at: Utc::now()
+                + Duration::seconds(body.expires_in.unwrap_or(DEFAULT_TOKEN_LIFETIME)),
+            token_url: self.token_url.clone(),
+        };
+        files::write_private(&self.cache_path, serde_json::to_string(&token)?.as_bytes())?;
+        let access_token = token.access_token.clone();
+        *self.token.lock().unwrap() = Some(token);
+        Ok(access_token)
+    }
+}
+
+/// The token cache is best-effort: an empty or corrupt file just means logging in again.
+fn read_token(path: &Path) -> Option<Token> {
+    let content = fs::read_to_string(path).ok()?;
+    serde_json::from_str(&content).ok()
+}
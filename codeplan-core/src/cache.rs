@@ -0,0 +1,17 @@
+use std::fs;
+
+use serde::de::DeserializeOwned;
+
+use crate::error::Result;
+
+// JSON files written by versions before the local store, only read to import them.
+pub const TASK_PATH: &str = "./cache/task.json";
+pub const COMMENT_PATH: &str = "./cache/comment.json";
+pub const PROJECT_PATH: &str = "./cache/project.json";
+pub const OUTBOX_PATH: &str = "./cache/outbox.json";
+
+pub fn read<T: DeserializeOwned>(path: &str) -> Result<Vec<T>> {
+    let db_content = fs::read_to_string(path)?;
+    let parsed: Vec<T> = serde_json::from_str(&db_content)?;
+    Ok(parsed)
+}
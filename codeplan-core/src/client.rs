@@ -0,0 +1,461 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use reqwest::header::{
+    HeaderMap, HeaderName, ACCEPT, ETAG, IF_MODIFIED_SINCE, IF_NONE_MATCH, LAST_MODIFIED,
+};
+use reqwest::{RequestBuilder, Response, StatusCode};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::{Auth, Credentials};
+use crate::config::Settings;
+use crate::error::{Error, Result};
+use crate::models::{
+    Comment, CommentPatch, NewComment, NewProject, NewTask, Project, ProjectPatch, Task, TaskPatch,
+};
+
+pub const DEFAULT_SERVER: &str = "http://172.30.152.201:4000";
+
+/// Limits so a stalled server fails the sync instead of holding the store lock forever.
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Where the previous sync of a resource left off.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Cursor {
+    #[serde(default)]
+    pub etag: Option<String>,
+    #[serde(default)]
+    pub last_modified: Option<String>,
+    #[serde(default)]
+    pub updated_since: Option<String>,
+}
+
+/// What changed on the server since a `Cursor`.
+#[derive(Debug)]
+pub enum Changes<T> {
+    NotModified,
+    /// The server ignored the cursor and sent everything.
+    Full(Vec<T>),
+    Delta { items: Vec<T>, deleted: Vec<usize> },
+}
+
+#[derive(Debug)]
+pub struct Fetched<T> {
+    pub changes: Changes<T>,
+    pub cursor: Cursor,
+}
+
+/// Servers that understand `updated_since` answer with an object, older ones with a plain list.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ChangesBody<T> {
+    Delta {
+        items: Vec<T>,
+        #[serde(default)]
+        deleted: Vec<usize>,
+        #[serde(default)]
+        cursor: Option<String>,
+    },
+    Full(Vec<T>),
+}
+
+/// Body of a 422, either `{"errors": {field: [..]}}` or the field map itself.
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ValidationBody {
+    Wrapped { errors: BTreeMap<String, Vec<String>> },
+    Fields(BTreeMap<String, Vec<String>>),
+}
+
+/// Typed client for the Codeplan REST API.
+#[derive(Clone)]
+pub struct Client {
+    base_url: String,
+    http: reqwest::Client,
+    auth: Option<Arc<Auth>>,
+}
+
+impl Client {
+    /// Unauthenticated client, e.g. for a local stand-in server.
+    pub fn new(base_url: &str) -> Client {
+        Client {
+            base_url: base_url.trim_end_matches('/').to_owned(),
+            http: reqwest::Client::builder()
+                .connect_timeout(CONNECT_TIMEOUT)
+                .timeout(REQUEST_TIMEOUT)
+                .build()
+                .expect("the HTTP client only fails to build without a TLS backend"),
+            auth: None,
+        }
+    }
+
+    /// Client for the resolved settings, logging in with `client.json` when it is filled in.
+    pub fn from_settings(settings: &Settings) -> Result<Client> {
+        let mut client = Client::new(&settings.server);
+        if let Some(credentials) = Credentials::load(&settings.credentials_path)? {
+            client.auth = Some(Arc::new(Auth::new(
+                credentials,
+                &settings.token_url,
+                &settings.token_path,
+            )));
+        }
+        Ok(client)
+    }
+
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    fn url(&self, path: &str) -> String {
+        format!("{}/{}", self.base_url, path)
+    }
+
+    async fn authorize(&self, req: RequestBuilder) -> Result<RequestBuilder> {
+        let req = req.header(ACCEPT, "application/json");
+        match &self.auth {
+            Some(auth) => Ok(req.bearer_auth(auth.token(&self.http).await?)),
+            None => Ok(req),
+        }
+    }
+
+    /// Sends the request built by `build`, logging in again and retrying once on a 401.
+    async fn execute<F>(&self, build: F) -> Result<Response>
+    where
+        F: Fn(&reqwest::Client) -> RequestBuilder,
+    {
+        let mut res = self.authorize(build(&self.http)).await?.send().await?;
+        if res.status() == StatusCode::UNAUTHORIZED {
+            if let Some(auth) = &self.auth {
+                let token = auth.refresh(&self.http).await?;
+                res = build(&self.http)
+                    .header(ACCEPT, "application/json")
+                    .bearer_auth(token)
+                    .send()
+                    .await?;
+            }
+        }
+        Ok(res)
+    }
+
+    async fn send<F>(&self, build: F) -> Result<Response>
+    where
+        F: Fn(&reqwest::Client) -> RequestBuilder,
+    {
+        let res = self.execute(build).await?;
+        if res.status() == StatusCode::UNPROCESSABLE_ENTITY {
+            let url = res.url().to_string();
+            let fields = match res.json::<ValidationBody>().await {
+                Ok(ValidationBody::Wrapped { errors }) => errors,
+                Ok(ValidationBody::Fields(fields)) => fields,
+                Err(_) => BTreeMap::new(),
+            };
+            return Err(Error::ValidationError { url, fields });
+        }
+        if !res.status().is_success() {
+            return Err(Error::StatusError {
+                url: res.url().to_string(),
+                status: res.status(),
+            });
+        }
+        Ok(res)
+    }
+
+    async fn fetch<T, F>(&self, build: F) -> Result<T>
+    where
+        T: DeserializeOwned,
+        F: Fn(&reqwest::Client) -> RequestBuilder,
+    {
+        let res = self.send(build).await?;
+        Ok(res.json().await?)
+    }
+
+    /// Lists what changed since `cursor`, using conditional requests and `updated_since`.
+    ///
+    /// A server that rejects the cursor gets asked once more without it, for everything.
+    async fn fetch_changes<T: DeserializeOwned>(&self, path: &str, cursor: &Cursor) -> Result<Fetched<T>> {
+        match self.fetch_changes_since(path, cursor).await {
+            Err(Error::StatusError { status, .. }) if status.is_client_error() && cursor.updated_since.is_some() => {
+                self.fetch_changes_since(path, &Cursor::default()).await
+            }
+            fetched => fetched,
+        }
+    }
+
+    async fn fetch_changes_since<T: DeserializeOwned>(&self, path: &str, cursor: &Cursor) -> Result<Fetched<T>> {
+        let url = self.url(path);
+        let res = self
+            .execute(|http| {
+                let mut req = http.get(&url);
+                if let Some(updated_since) = &cursor.updated_since {
+                    req = req.query(&[("updated_since", updated_since)]);
+                }
+                if let Some(etag) = &cursor.etag {
+                    req = req.header(IF_NONE_MATCH, etag.as_str());
+                }
+                if let Some(last_modified) = &cursor.last_modified {
+                    req = req.header(IF_MODIFIED_SINCE, last_modified.as_str());
+                }
+                req
+            })
+            .await?;
+
+        if res.status() == StatusCode::NOT_MODIFIED {
+            return Ok(Fetched {
+                changes: Changes::NotModified,
+                cursor: cursor.clone(),
+            });
+        }
+        if !res.status().is_success() {
+            return Err(Error::StatusError {
+                url: res.url().to_string(),
+                status: res.status(),
+            });
+        }
+
+        let headers = res.headers().clone();
+        // Items are read as JSON first to find their `updated_at`, which the models don't keep.
+        let body = res.json::<ChangesBody<serde_json::Value>>().await?;
+        let (changes, updated_since) = read_changes(body, cursor)?;
+        Ok(Fetched {
+            changes,
+            cursor: Cursor {
+                etag: header(&headers, ETAG),
+                last_modified: header(&headers, LAST_MODIFIED),
+                updated_since,
+            },
+        })
+    }
+
+    pub async fn list_tasks(&self) -> Result<Vec<Task>> {
+        let url = self.url("tasks/");
+        self.fetch(|http| http.get(&url)).await
+    }
+
+    pub async fn list_tasks_since(&self, cursor: &Cursor) -> Result<Fetched<Task>> {
+        self.fetch_changes("tasks/", cursor).await
+    }
+
+    pub async fn get_task(&self, id: usize) -> Result<Task> {
+        let url = self.url(&format!("tasks/{}", id));
+        self.fetch(|http| http.get(&url)).await
+    }
+
+    pub async fn create_task(&self, task: &NewTask) -> Result<Task> {
+        let url = self.url("tasks/");
+        self.fetch(|http| http.post(&url).json(task)).await
+    }
+
+    pub async fn update_task(&self, id: usize, patch: &TaskPatch) -> Result<Task> {
+        let url = self.url(&format!("tasks/{}", id));
+        self.fetch(|http| http.patch(&url).json(patch)).await
+    }
+
+    pub async fn complete_task(&self, id: usize) -> Result<()> {
+        let url = self.url(&format!("tasks/{}/complete", id));
+        self.send(|http| http.post(&url)).await?;
+        Ok(())
+    }
+
+    /// Undoes `complete_task`, the task goes back to pending.
+    pub async fn reopen_task(&self, id: usize) -> Result<()> {
+        let url = self.url(&format!("tasks/{}/reopen", id));
+        self.send(|http| http.post(&url)).await?;
+        Ok(())
+    }
+
+    pub async fn delete_task(&self, id: usize) -> Result<()> {
+        let url = self.url(&format!("tasks/{}", id));
+        self.send(|http| http.delete(&url)).await?;
+        Ok(())
+    }
+
+    pub async fn list_comments(&self) -> Result<Vec<Comment>> {
+        let url = self.url("tasks/comments/");
+        self.fetch(|http| http.get(&url)).await
+    }
+
+    pub async fn list_comments_since(&self, cursor: &Cursor) -> Result<Fetched<Comment>> {
+        self.fetch_changes("tasks/comments/", cursor).await
+    }
+
+    pub async fn get_comment(&self, id: usize) -> Result<Comment> {
+        let url = self.url(&format!("tasks/comments/{}", id));
+        self.fetch(|http| http.get(&url)).await
+    }
+
+    pub async fn create_comment(&self, comment: &NewComment) -> Result<Comment> {
+        let url = self.url("tasks/comments/");
+        self.fetch(|http| http.post(&url).json(comment)).await
+    }
+
+    pub async fn update_comment(&self, id: usize, patch: &CommentPatch) -> Result<Comment> {
+        let url = self.url(&format!("tasks/comments/{}", id));
+        self.fetch(|http| http.patch(&url).json(patch)).await
+    }
+
+    pub async fn delete_comment(&self, id: usize) -> Result<()> {
+        let url = self.url(&format!("tasks/comments/{}", id));
+        self.send(|http| http.delete(&url)).await?;
+        Ok(())
+    }
+
+    pub async fn list_projects(&self) -> Result<Vec<Project>> {
+        let url = self.url("projects/");
+        self.fetch(|http| http.get(&url)).await
+    }
+
+    pub async fn list_projects_since(&self, cursor: &Cursor) -> Result<Fetched<Project>> {
+        self.fetch_changes("projects/", cursor).await
+    }
+
+    pub async fn get_project(&self, id: usize) -> Result<Project> {
+        let url = self.url(&format!("projects/{}", id));
+        self.fetch(|http| http.get(&url)).await
+    }
+
+    pub async fn create_project(&self, project: &NewProject) -> Result<Project> {
+        let url = self.url("projects/");
+        self.fetch(|http| http.post(&url).json(project)).await
+    }
+
+    pub async fn update_project(&self, id: usize, patch: &ProjectPatch) -> Result<Project> {
+        let url = self.url(&format!("projects/{}", id));
+        self.fetch(|http| http.patch(&url).json(patch)).await
+    }
+
+    pub async fn delete_project(&self, id: usize) -> Result<()> {
+        let url = self.url(&format!("projects/{}", id));
+        self.send(|http| http.delete(&url)).await?;
+        Ok(())
+    }
+}
+
+/// The changes in `body` and the `updated_since` to send next time, for a request made with `cursor`.
+fn read_changes<T: DeserializeOwned>(
+    body: ChangesBody<serde_json::Value>,
+    cursor: &Cursor,
+) -> Result<(Changes<T>, Option<String>)> {
+    match body {
+        ChangesBody::Delta { items, deleted, cursor: body_cursor } => {
+            let updated_since = body_cursor
+                .or_else(|| latest_update(&items))
+                .or_else(|| cursor.updated_since.clone());
+            let items = parse_items(items)?;
+            // Without a cursor the server sent every record, not just the changed ones.
+            let changes = if cursor.updated_since.is_some() {
+                Changes::Delta { items, deleted }
+            } else {
+                Changes::Full(items)
+            };
+            Ok((changes, updated_since))
+        }
+        ChangesBody::Full(items) => Ok((Changes::Full(parse_items(items)?), None)),
+    }
+}
+
+fn parse_items<T: DeserializeOwned>(items: Vec<serde_json::Value>) -> Result<Vec<T>> {
+    let mut parsed = Vec::with_capacity(items.len());
+    for item in items {
+        parsed.push(serde_json::from_value(item)?);
+    }
+    Ok(parsed)
+}
+
+/// The newest `updated_at` among `items`, to ask for what changed after it next time.
+fn latest_update(items: &[serde_json::Value]) -> Option<String> {
+    items
+        .iter()
+        .filter_map(|item| item.get("updated_at")?.as_str())
+        .filter_map(|updated_at| DateTime::parse_from_rfc3339(updated_at).ok())
+        .max()
+        .map(|updated_at| updated_at.with_timezone(&Utc).to_rfc3339())
+}
+
+fn header(headers: &HeaderMap, name: HeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::{json, Value};
+
+    use super::*;
+
+    fn since(updated_since: &str) -> Cursor {
+        Cursor {
+            updated_since: Some(updated_since.to_owned()),
+            ..Cursor::default()
+        }
+    }
+
+    fn read(body: Value, cursor: &Cursor) -> (Changes<Value>, Option<String>) {
+        read_changes(serde_json::from_value(body).unwrap(), cursor).unwrap()
+    }
+
+    fn ids(items: &[Value]) -> Vec<u64> {
+        items.iter().map(|item| item["id"].as_u64().unwrap()).collect()
+    }
+
+    #[test]
+    fn plain_list_is_full() {
+        let (changes, updated_since) = read(json!([{"id": 1}, {"id": 2}]), &since("2024-05-01T00:00:00+00:00"));
+        match changes {
+            Changes::Full(items) => assert_eq!(ids(&items), vec![1, 2]),
+            other => panic!("expected Full, got {:?}", other),
+        }
+        assert_eq!(updated_since, None);
+    }
+
+    #[test]
+    fn object_with_cursor_is_delta() {
+        let body = json!({"items": [{"id": 1}], "deleted": [7, 8], "cursor": "2024-05-03T00:00:00Z"});
+        let (changes, updated_since) = read(body, &since("2024-05-01T00:00:00+00:00"));
+        match changes {
+            Changes::Delta { items, deleted } => {
+                assert_eq!(ids(&items), vec![1]);
+                assert_eq!(deleted, vec![7, 8]);
+            }
+            other => panic!("expected Delta, got {:?}", other),
+        }
+        assert_eq!(updated_since.as_deref(), Some("2024-05-03T00:00:00Z"));
+    }
+
+    #[test]
+    fn object_without_request_cursor_is_full() {
+        let body = json!({"items": [{"id": 1}], "cursor": "2024-05-03T00:00:00Z"});
+        let (changes, _) = read(body, &Cursor::default());
+        match changes {
+            Changes::Full(items) => assert_eq!(ids(&items), vec![1]),
+            other => panic!("expected Full, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn cursor_falls_back_to_latest_update() {
+        let body = json!({"items": [
+            {"id": 1, "updated_at": "2024-05-01T10:00:00Z"},
+            {"id": 2, "updated_at": "2024-05-02T09:00:00-03:00"},
+            {"id": 3, "updated_at": "not a date"},
+            {"id": 4},
+        ]});
+        let (_, updated_since) = read(body, &since("2024-04-30T00:00:00+00:00"));
+        assert_eq!(updated_since.as_deref(), Some("2024-05-02T12:00:00+00:00"));
+    }
+
+    #[test]
+    fn cursor_kept_when_nothing_changed() {
+        let (changes, updated_since) = read(json!({"items": []}), &since("2024-05-01T00:00:00+00:00"));
+        match changes {
+            Changes::Delta { items, deleted } => assert!(items.is_empty() && deleted.is_empty()),
+            other => panic!("expected Delta, got {:?}", other),
+        }
+        assert_eq!(updated_since.as_deref(), Some("2024-05-01T00:00:00+00:00"));
+    }
+}
@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::auth::{CREDENTIALS_FILE, TOKEN_FILE};
+use crate::client::DEFAULT_SERVER;
+use crate::error::{Error, Result};
+use crate::files::LOCK_FILE;
+use crate::store::DB_FILE;
+
+pub const SERVER_ENV: &str = "CODEPLAN_SERVER";
+pub const DEFAULT_PROFILE: &str = "production";
+pub const DEFAULT_DUE_SOON_DAYS: i64 = 3;
+
+/// Contents of `config.toml`, shared by every Codeplan binary.
+///
+/// ```toml
+/// default_profile = "staging"
+/// always_mask = true
+/// due_soon_days = 5
+///
+/// [profiles.staging]
+/// server = "http://staging.codeplan.local:4000"
+/// credentials = "/home/me/.config/codeplan/staging-client.json"
+/// ```
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Config {
+    #[serde(default)]
+    pub default_profile: Option<String>,
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
+    /// Never show customer documents and contacts in clear text, e.g. on shared screens.
+    #[serde(default)]
+    pub always_mask: bool,
+    /// Tasks due within this many days are flagged as due soon, 3 when unset.
+    #[serde(default)]
+    pub due_soon_days: Option<i64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Profile {
+    pub server: String,
+    /// OAuth token endpoint, defaults to `<server>/oauth/token`.
+    #[serde(default)]
+    pub token_url: Option<String>,
+    /// `client.json` holding the client credentials.
+    #[serde(default)]
+    pub credentials: Option<PathBuf>,
+    /// Where the bearer token is cached between runs.
+    #[serde(default)]
+    pub token_cache: Option<PathBuf>,
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/codeplan` (or the platform equivalent), holding `config.toml`
+    /// and the default `client.json`.
+    pub fn dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("codeplan"))
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        Config::dir().map(|dir| dir.join("config.toml"))
+    }
+
+    /// Reads the config file, falling back to the defaults when it does not exist.
+    pub fn load() -> Result<Config> {
+        let path = match Config::path() {
+            Some(path) => path,
+            None => return Ok(Config::default()),
+        };
+        match fs::read_to_string(&path) {
+            Ok(content) => Ok(toml::from_str(&content)?),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Config::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn due_soon_days(&self) -> i64 {
+        self.due_soon_days.unwrap_or(DEFAULT_DUE_SOON_DAYS)
+    }
+
+    fn profile(&self, name: &str) -> Option<Profile> {
+        if let Some(profile) = self.profiles.get(name) {
+            return Some(profile.clone());
+        }
+        let server = match name {
+            "production" => DEFAULT_SERVER,
+            "local" => "http://localhost:4000",
+            _ => return None,
+        };
+        Some(Profile {
+            server: server.to_owned(),
+            token_url: None,
+            credentials: None,
+            token_cache: None,
+        })
+    }
+}
+
+/// Settings resolved from the command line, the environment and the config file.
+#[derive(Clone, Debug)]
+pub struct Settings {
+    pub profile: String,
+    pub server: String,
+    pub token_url: String,
+    pub credentials_path: PathBuf,
+    pub token_path: PathBuf,
+    /// Where the local store of `server` lives, see `data_dir`.
+    pub data_dir: PathBuf,
+    pub config: Config,
+}
+
+impl Settings {
+    /// Resolves the active profile and server, in order of precedence:
+    /// `--server`, `CODEPLAN_SERVER`, then the server of the selected profile.
+    pub fn from_args(args: &[String]) -> Result<Settings> {
+        let config = Config::load()?;
+        let profile = flag_value(args, "--profile")
+            .map(String::from)
+            .or_else(|| config.default_profile.clone())
+            .unwrap_or_else(|| DEFAULT_PROFILE.to_owned());
+
+        let selected = config.profile(&profile);
+
+        let server = match flag_value(args, "--server") {
+            Some(server) => server.to_owned(),
+            None => match env::var(SERVER_ENV) {
+                Ok(server) if !server.is_empty() => server,
+                _ => selected
+                    .as_ref()
+                    .ok_or_else(|| Error::UnknownProfileError(profile.clone()))?
+                    .server
+                    .clone(),
+            },
+        };
+        let token_url = selected
+            .as_ref()
+            .and_then(|p| p.token_url.clone())
+            .unwrap_or_else(|| format!("{}/oauth/token", server.trim_end_matches('/')));
+        let credentials_path = selected
+            .as_ref()
+            .and_then(|p| p.credentials.clone())
+            .unwrap_or_else(|| Config::dir().unwrap_or_default().join(CREDENTIALS_FILE));
+        let data_dir = data_dir(&server);
+        let token_path = selected
+            .as_ref()
+            .and_then(|p| p.token_cache.clone())
+            .unwrap_or_else(|| data_dir.join(TOKEN_FILE));
+
+        Ok(Settings {
+            profile,
+            server,
+            token_url,
+            credentials_path,
+            token_path,
+            data_dir,
+            config,
+        })
+    }
+
+    pub fn db_path(&self) -> PathBuf {
+        self.data_dir.join(DB_FILE)
+    }
+
+    /// Lock shared by every process using the store at `db_path`.
+    pub fn lock_path(&self) -> PathBuf {
+        self.data_dir.join(LOCK_FILE)
+    }
+}
+
+/// `$XDG_DATA_HOME/codeplan/<server>` (or the platform equivalent).
+///
+/// Each server gets its own store, so switching profiles never mixes records,
+/// sync cursors or queued changes of different servers.
+fn data_dir(server: &str) -> PathBuf {
+    let host = server.find("://").map_or(server, |index| &server[index + 3..]);
+    let key: String = host
+        .trim_end_matches('/')
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+        .collect();
+    dirs::data_dir()
+        .map(|dir| dir.join("codeplan"))
+        .unwrap_or_else(|| PathBuf::from("./cache"))
+        .join(key)
+}
+
+/// Value following `flag` in `args`, e.g. `flag_value(&args, "--server")`.
+pub fn flag_value<'a>(args: &'a [String], flag: &str) -> Option<&'a str> {
+    args.iter()
+        .position(|arg| arg == flag)
+        .and_then(|index| args.get(index + 1))
+        .map(String::as_str)
+}
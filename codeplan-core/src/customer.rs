@@ -0,0 +1,260 @@
+use std::fmt;
+
+/// A customer's CPF or CNPJ, holding only the digits.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Document {
+    Cpf(String),
+    Cnpj(String),
+}
+
+impl Document {
+    /// Accepts the document with or without punctuation, checking its check digits.
+    pub fn parse(input: &str) -> Option<Document> {
+        let digits = digits(input);
+        match digits.len() {
+            11 if check_digits(&digits, &[10, 9, 8, 7, 6, 5, 4, 3, 2], &[11, 10, 9, 8, 7, 6, 5, 4, 3, 2], cpf_digit) => {
+                Some(Document::Cpf(digits))
+            }
+            14 if check_digits(
+                &digits,
+                &[5, 4, 3, 2, 9, 8, 7, 6, 5, 4, 3, 2],
+                &[6, 5, 4, 3, 2, 9, 8, 7, 6, 5, 4, 3, 2],
+                cnpj_digit,
+            ) =>
+            {
+                Some(Document::Cnpj(digits))
+            }
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Document {
+    /// `123.456.789-09` or `12.345.678/0001-95`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Document::Cpf(d) => write!(f, "{}.{}.{}-{}", &d[0..3], &d[3..6], &d[6..9], &d[9..11]),
+            Document::Cnpj(d) => write!(
+                f,
+                "{}.{}.{}/{}-{}",
+                &d[0..2],
+                &d[2..5],
+                &d[5..8],
+                &d[8..12],
+                &d[12..14]
+            ),
+        }
+    }
+}
+
+/// How to reach a customer.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Contact {
+    Email(String),
+    /// Brazilian number with area code, digits only.
+    Phone(String),
+}
+
+impl Contact {
+    pub fn parse(input: &str) -> Option<Contact> {
+        let input = input.trim();
+        if input.contains('@') {
+            return parse_email(input).map(Contact::Email);
+        }
+        if input.chars().any(|c| !(c.is_ascii_digit() || " ()-+.".contains(c))) {
+            return None;
+        }
+        let mut digits = digits(input);
+        if input.starts_with('+') || digits.len() > 11 {
+            digits = digits.strip_prefix("55")?.to_owned();
+        }
+        match digits.len() {
+            10 | 11 if !digits.starts_with('0') => Some(Contact::Phone(digits)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Contact {
+    /// E-mails as typed (lowercased), phones as `(11) 91234-5678`.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Contact::Email(email) => write!(f, "{}", email),
+            Contact::Phone(d) => {
+                let split = d.len() - 4;
+                write!(f, "({}) {}-{}", &d[0..2], &d[2..split], &d[split..])
+            }
+        }
+    }
+}
+
+/// `***.456.789-**` for a CPF, `**.345.678/****-**` for a CNPJ.
+pub fn mask_document(value: &str) -> String {
+    match Document::parse(value) {
+        Some(Document::Cpf(d)) => format!("***.{}.{}-**", &d[3..6], &d[6..9]),
+        Some(Document::Cnpj(d)) => format!("**.{}.{}/****-**", &d[2..5], &d[5..8]),
+        None => mask_all(value),
+    }
+}
+
+/// `j***@example.com` for an e-mail, `(11) *****-5678` for a phone.
+pub fn mask_contact(value: &str) -> String {
+    match Contact::parse(value) {
+        Some(Contact::Email(email)) => {
+            let (local, domain) = email.split_at(email.find('@').unwrap_or(0));
+            let first: String = local.chars().take(1).collect();
+            format!("{}***{}", first, domain)
+        }
+        Some(Contact::Phone(d)) => {
+            let split = d.len() - 4;
+            format!("({}) {}-{}", &d[0..2], "*".repeat(split - 2), &d[split..])
+        }
+        None => mask_all(value),
+    }
+}
+
+/// Hides every letter and digit but the last two, for values in no known format.
+fn mask_all(value: &str) -> String {
+    let hidden = value.chars().filter(|c| c.is_alphanumeric()).count().saturating_sub(2);
+    let mut seen = 0;
+    value
+        .chars()
+        .map(|c| {
+            if !c.is_alphanumeric() {
+                return c;
+            }
+            seen += 1;
+            if seen <= hidden {
+                '*'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+fn parse_email(input: &str) -> Option<String> {
+    let mut parts = input.split('@');
+    let (local, domain) = (parts.next()?, parts.next()?);
+    let valid = parts.next().is_none()
+        && !local.is_empty()
+        && !input.contains(char::is_whitespace)
+        && domain.split('.').count() >= 2
+        && domain.split('.').all(|label| !label.is_empty());
+    if valid {
+        Some(input.to_lowercase())
+    } else {
+        None
+    }
+}
+
+fn digits(input: &str) -> String {
+    input.chars().filter(|c| c.is_ascii_digit()).collect()
+}
+
+fn cpf_digit(sum: u32) -> u32 {
+    (sum * 10 % 11) % 10
+}
+
+fn cnpj_digit(sum: u32) -> u32 {
+    match sum % 11 {
+        rest if rest < 2 => 0,
+        rest => 11 - rest,
+    }
+}
+
+/// Checks the two trailing check digits, rejecting repeated digits like `111.111.111-11`.
+fn check_digits(digits: &str, first: &[u32], second: &[u32], digit: fn(u32) -> u32) -> bool {
+    let values: Vec<u32> = digits.chars().filter_map(|c| c.to_digit(10)).collect();
+    if values.iter().all(|value| *value == values[0]) {
+        return false;
+    }
+    let weighted = |weights: &[u32]| -> u32 { weights.iter().zip(&values).map(|(w, v)| w * v).sum() };
+    digit(weighted(first)) == values[first.len()] && digit(weighted(second)) == values[second.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cpf(digits: &str) -> Option<Document> {
+        Some(Document::Cpf(digits.to_owned()))
+    }
+
+    fn cnpj(digits: &str) -> Option<Document> {
+        Some(Document::Cnpj(digits.to_owned()))
+    }
+
+    fn phone(digits: &str) -> Option<Contact> {
+        Some(Contact::Phone(digits.to_owned()))
+    }
+
+    #[test]
+    fn parses_valid_documents() {
+        assert_eq!(Document::parse("529.982.247-25"), cpf("52998224725"));
+        assert_eq!(Document::parse("52998224725"), cpf("52998224725"));
+        assert_eq!(Document::parse(" 529 982 247 25 "), cpf("52998224725"));
+        assert_eq!(Document::parse("11.222.333/0001-81"), cnpj("11222333000181"));
+        assert_eq!(Document::parse("11222333000181"), cnpj("11222333000181"));
+    }
+
+    #[test]
+    fn rejects_invalid_documents() {
+        for input in &[
+            "111.111.111-11",
+            "000.000.000-00",
+            "11.111.111/1111-11",
+            "529.982.247-26",
+            "529.982.247-35",
+            "11.222.333/0001-82",
+            "11.222.333/0001-91",
+            "529.982.247-2",
+            "",
+        ] {
+            assert_eq!(Document::parse(input), None, "{}", input);
+        }
+    }
+
+    #[test]
+    fn formats_documents() {
+        assert_eq!(cpf("52998224725").unwrap().to_string(), "529.982.247-25");
+        assert_eq!(cnpj("11222333000181").unwrap().to_string(), "11.222.333/0001-81");
+    }
+
+    #[test]
+    fn parses_phones() {
+        assert_eq!(Contact::parse("(11) 91234-5678"), phone("11912345678"));
+        assert_eq!(Contact::parse("+55 11 91234-5678"), phone("11912345678"));
+        assert_eq!(Contact::parse("5511912345678"), phone("11912345678"));
+        assert_eq!(Contact::parse("11 3123.4567"), phone("1131234567"));
+        assert_eq!(phone("11912345678").unwrap().to_string(), "(11) 91234-5678");
+        assert_eq!(phone("1131234567").unwrap().to_string(), "(11) 3123-4567");
+    }
+
+    #[test]
+    fn rejects_invalid_phones() {
+        for input in &["(01) 91234-5678", "91234-5678", "+1 415 555 0100", "(11) 9123x-5678", "119123456789"] {
+            assert_eq!(Contact::parse(input), None, "{}", input);
+        }
+    }
+
+    #[test]
+    fn parses_emails() {
+        assert_eq!(
+            Contact::parse(" Joao@Example.com "),
+            Some(Contact::Email(String::from("joao@example.com")))
+        );
+        for input in &["joao@", "@example.com", "joao@example", "joao@@example.com", "jo ao@example.com", "joao@example..com"] {
+            assert_eq!(Contact::parse(input), None, "{}", input);
+        }
+    }
+
+    #[test]
+    fn masks_customers() {
+        assert_eq!(mask_document("529.982.247-25"), "***.982.247-**");
+        assert_eq!(mask_document("11.222.333/0001-81"), "**.222.333/****-**");
+        assert_eq!(mask_document("abc-123"), "***-*23");
+        assert_eq!(mask_contact("joao@example.com"), "j***@example.com");
+        assert_eq!(mask_contact("(11) 91234-5678"), "(11) *****-5678");
+    }
+}
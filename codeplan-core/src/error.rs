@@ -0,0 +1,75 @@
+use std::collections::BTreeMap;
+use std::io;
+
+use thiserror::Error;
+
+use crate::config::Config;
+
+// Every variant keeps the `Error` suffix of the original `ReadDBError` and `ParseDBError`.
+#[allow(clippy::enum_variant_names)]
+#[derive(Error, Debug)]
+pub enum Error {
+    #[error("error reading the DB file: {0}")]
+    ReadDBError(#[from] io::Error),
+    #[error("error parsing the DB file: {0}")]
+    ParseDBError(#[from] serde_json::Error),
+    #[error("error accessing the local store: {0}")]
+    StoreError(#[from] rusqlite::Error),
+    #[error("error talking to the server: {0}")]
+    RequestError(#[from] reqwest::Error),
+    #[error("server answered {status} for {url}")]
+    StatusError { url: String, status: reqwest::StatusCode },
+    #[error("server rejected the data sent to {url}: {}", describe_fields(.fields))]
+    ValidationError { url: String, fields: BTreeMap<String, Vec<String>> },
+    #[error("login rejected with {status} by {url}, check client.json")]
+    AuthError { url: String, status: reqwest::StatusCode },
+    #[error("error parsing the config file: {0}")]
+    ConfigError(#[from] toml::de::Error),
+    #[error("unknown profile '{0}', add it to the config file")]
+    UnknownProfileError(String),
+}
+
+impl Error {
+    /// Whether the failure is worth retrying later, e.g. the server is unreachable.
+    ///
+    /// Only 4xx answers are rejections; a request that got no answer at all, from a
+    /// timeout to a connection reset mid-body, is retried.
+    pub fn is_transient(&self) -> bool {
+        match self {
+            Error::RequestError(e) => match e.status() {
+                Some(status) => !status.is_client_error(),
+                None => !e.is_builder(),
+            },
+            Error::StatusError { status, .. } | Error::AuthError { status, .. } => {
+                !status.is_client_error()
+            }
+            _ => false,
+        }
+    }
+
+    /// The endpoint or file involved, when the error knows it.
+    ///
+    /// Store errors don't: the database depends on the server in use, see `Settings::db_path`.
+    pub fn location(&self) -> Option<String> {
+        match self {
+            Error::RequestError(e) => e.url().map(|url| url.to_string()),
+            Error::StatusError { url, .. }
+            | Error::ValidationError { url, .. }
+            | Error::AuthError { url, .. } => Some(url.clone()),
+            Error::ConfigError(_) | Error::UnknownProfileError(_) => {
+                Config::path().map(|path| path.display().to_string())
+            }
+            _ => None,
+        }
+    }
+}
+
+fn describe_fields(fields: &BTreeMap<String, Vec<String>>) -> String {
+    fields
+        .iter()
+        .map(|(field, errors)| format!("{} {}", field, errors.join(", ")))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
@@ -0,0 +1,95 @@
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, prelude::*};
+use std::path::{Path, PathBuf};
+use std::process;
+
+use fs2::FileExt;
+
+use crate::error::Result;
+
+/// Name of the lock next to the store, see `Settings::lock_path`.
+pub const LOCK_FILE: &str = "sync.lock";
+
+/// Writes `contents` to a temporary file next to `path` and renames it into place,
+/// so readers see either the old or the new content, never a partial write.
+///
+/// Only the owner may read the file, which is meant for secrets like the bearer token.
+pub fn write_private(path: &Path, contents: &[u8]) -> Result<()> {
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+
+    let mut tmp = OsString::from(path.as_os_str());
+    tmp.push(format!(".{}.partial", process::id()));
+    let tmp = PathBuf::from(tmp);
+
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    // The mode only applies to new files, so don't reuse one left behind by a crash.
+    let _ = fs::remove_file(&tmp);
+    let written = options.open(&tmp).and_then(|mut file| {
+        file.write_all(contents)?;
+        file.sync_all()
+    });
+    if let Err(e) = written.and_then(|_| fs::rename(&tmp, path)) {
+        let _ = fs::remove_file(&tmp);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Advisory lock on the local store, shared by `codeplan-updater` and the TUI.
+///
+/// A sync holds it exclusively while it writes; readers take it shared so they
+/// never load a store that is halfway through a sync. Released on drop.
+pub struct StoreLock {
+    file: File,
+}
+
+impl StoreLock {
+    fn open(path: &Path) -> io::Result<File> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        OpenOptions::new().create(true).write(true).open(path)
+    }
+
+    /// Waits until no one else holds the lock at `path`.
+    pub fn exclusive(path: &Path) -> Result<StoreLock> {
+        let file = StoreLock::open(path)?;
+        file.lock_exclusive()?;
+        Ok(StoreLock { file })
+    }
+
+    /// Exclusive lock, or `None` if a sync is already running.
+    pub fn try_exclusive(path: &Path) -> Result<Option<StoreLock>> {
+        let file = StoreLock::open(path)?;
+        match file.try_lock_exclusive() {
+            Ok(()) => Ok(Some(StoreLock { file })),
+            Err(e) if e.kind() == fs2::lock_contended_error().kind() => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Shared lock, or `None` while a sync holds it exclusively.
+    pub fn try_shared(path: &Path) -> Result<Option<StoreLock>> {
+        let file = StoreLock::open(path)?;
+        match file.try_lock_shared() {
+            Ok(()) => Ok(Some(StoreLock { file })),
+            Err(e) if e.kind() == fs2::lock_contended_error().kind() => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+impl Drop for StoreLock {
+    fn drop(&mut self) {
+        let _ = self.file.unlock();
+    }
+}
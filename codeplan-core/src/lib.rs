@@ -0,0 +1,15 @@
+pub mod auth;
+pub mod cache;
+pub mod client;
+pub mod config;
+pub mod customer;
+pub mod error;
+pub mod files;
+pub mod models;
+pub mod outbox;
+pub mod store;
+pub mod sync;
+
+pub use client::Client;
+pub use error::{Error, Result};
+pub use models::{Comment, Project, Task};
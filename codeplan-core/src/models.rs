@@ -0,0 +1,296 @@
+use std::fmt;
+
+use chrono::prelude::*;
+use serde::{Deserialize, Deserializer, Serialize};
+
+use crate::customer::{mask_contact, mask_document};
+
+/// First of the ids given to records created offline, which the server hasn't numbered yet.
+///
+/// Each such record gets its own id from here up, see `unsynced_id`, so lists and
+/// searches can tell them apart until the next sync brings the server's ids.
+pub const UNSYNCED_ID: usize = usize::MAX / 2;
+
+/// Placeholder id of the record created by the outbox entry `entry_id`.
+pub fn unsynced_id(entry_id: u64) -> usize {
+    UNSYNCED_ID.saturating_add(entry_id as usize)
+}
+
+/// Whether `id` is a placeholder from `unsynced_id` rather than one from the server.
+pub fn is_unsynced(id: usize) -> bool {
+    id >= UNSYNCED_ID
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Task {
+    pub id: usize,
+    pub project: String,
+    pub content_preview: String,
+    pub content: String,
+    pub begin_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+    /// `None` until the task is done.
+    #[serde(default, deserialize_with = "deserialize_finish_date")]
+    pub finish_date: Option<DateTime<Utc>>,
+    /// As sent by the server, `None` when it sent none or one we don't know; see `Task::status`.
+    #[serde(default, deserialize_with = "deserialize_status")]
+    pub status: Option<TaskStatus>,
+}
+
+#[derive(Serialize, Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum TaskStatus {
+    Pending,
+    InProgress,
+    Done,
+    Cancelled,
+}
+
+impl TaskStatus {
+    /// Done and cancelled tasks need no more work.
+    pub fn is_closed(self) -> bool {
+        self == TaskStatus::Done || self == TaskStatus::Cancelled
+    }
+}
+
+/// Older servers (and the local cache they filled) use the Unix epoch for "not finished".
+fn deserialize_finish_date<'de, D>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let date = Option::<DateTime<Utc>>::deserialize(deserializer)?;
+    Ok(date.filter(|date| date.timestamp() > 0))
+}
+
+fn deserialize_status<'de, D>(deserializer: D) -> Result<Option<TaskStatus>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    let value = Option::<serde_json::Value>::deserialize(deserializer)?;
+    Ok(value.and_then(|value| serde_json::from_value(value).ok()))
+}
+
+/// Where a task stands against its deadline.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Deadline {
+    Done,
+    Cancelled,
+    Overdue,
+    /// Due within the configured window.
+    DueSoon,
+    OnTrack,
+}
+
+impl Task {
+    /// The server's status, or one inferred from `finish_date` when it sent none.
+    pub fn status(&self) -> TaskStatus {
+        match (self.status, self.finish_date) {
+            (Some(status), _) => status,
+            (None, Some(_)) => TaskStatus::Done,
+            (None, None) => TaskStatus::Pending,
+        }
+    }
+
+    pub fn deadline(&self, now: DateTime<Utc>, due_soon: chrono::Duration) -> Deadline {
+        let status = self.status();
+        if status == TaskStatus::Done {
+            Deadline::Done
+        } else if status == TaskStatus::Cancelled {
+            Deadline::Cancelled
+        } else if self.end_date < now {
+            Deadline::Overdue
+        } else if self.end_date <= now + due_soon {
+            Deadline::DueSoon
+        } else {
+            Deadline::OnTrack
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NewTask {
+    pub project: String,
+    pub content_preview: String,
+    pub content: String,
+    pub begin_date: DateTime<Utc>,
+    pub end_date: DateTime<Utc>,
+}
+
+/// Partial update for a task, only the fields set to `Some` are sent.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct TaskPatch {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content_preview: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub begin_date: Option<DateTime<Utc>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub end_date: Option<DateTime<Utc>>,
+}
+
+impl TaskPatch {
+    pub fn is_empty(&self) -> bool {
+        self.project.is_none()
+            && self.content_preview.is_none()
+            && self.content.is_none()
+            && self.begin_date.is_none()
+            && self.end_date.is_none()
+    }
+
+    pub fn apply(&self, task: &mut Task) {
+        if let Some(project) = &self.project {
+            task.project = project.clone();
+        }
+        if let Some(content_preview) = &self.content_preview {
+            task.content_preview = content_preview.clone();
+        }
+        if let Some(content) = &self.content {
+            task.content = content.clone();
+        }
+        if let Some(begin_date) = self.begin_date {
+            task.begin_date = begin_date;
+        }
+        if let Some(end_date) = self.end_date {
+            task.end_date = end_date;
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Comment {
+    pub id: usize,
+    /// Missing on comments synced before the server exposed it.
+    #[serde(default)]
+    pub task_id: Option<usize>,
+    pub task_preview: String,
+    pub content: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct NewComment {
+    pub task_id: usize,
+    pub task_preview: String,
+    pub content: String,
+}
+
+impl Comment {
+    /// Whether the comment belongs to `task`, by id or, for older comments, by preview.
+    pub fn is_on(&self, task: &Task) -> bool {
+        match self.task_id {
+            Some(task_id) => task_id == task.id,
+            None => self.task_preview == task.content_preview,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct CommentPatch {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub content: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct Project {
+    pub id: usize,
+    pub name: String,
+    pub customer_name: String,
+    pub customer_document: String,
+    pub customer_contact: String,
+    pub created_at: DateTime<Utc>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct NewProject {
+    pub name: String,
+    pub customer_name: String,
+    pub customer_document: String,
+    pub customer_contact: String,
+}
+
+/// Partial update for a project, only the fields set to `Some` are sent.
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct ProjectPatch {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub customer_name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub customer_document: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub customer_contact: Option<String>,
+}
+
+impl Project {
+    /// Copy with the customer's document and contact masked, for exports and screens.
+    pub fn masked(&self) -> Project {
+        Project {
+            customer_document: mask_document(&self.customer_document),
+            customer_contact: mask_contact(&self.customer_contact),
+            ..self.clone()
+        }
+    }
+}
+
+// Customer data is masked in `Debug` so it never ends up in logs in clear text.
+impl fmt::Debug for Project {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Project")
+            .field("id", &self.id)
+            .field("name", &self.name)
+            .field("customer_name", &self.customer_name)
+            .field("customer_document", &mask_document(&self.customer_document))
+            .field("customer_contact", &mask_contact(&self.customer_contact))
+            .field("created_at", &self.created_at)
+            .finish()
+    }
+}
+
+impl fmt::Debug for NewProject {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("NewProject")
+            .field("name", &self.name)
+            .field("customer_name", &self.customer_name)
+            .field("customer_document", &mask_document(&self.customer_document))
+            .field("customer_contact", &mask_contact(&self.customer_contact))
+            .finish()
+    }
+}
+
+impl fmt::Debug for ProjectPatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ProjectPatch")
+            .field("name", &self.name)
+            .field("customer_name", &self.customer_name)
+            .field("customer_document", &self.customer_document.as_deref().map(mask_document))
+            .field("customer_contact", &self.customer_contact.as_deref().map(mask_contact))
+            .finish()
+    }
+}
+
+impl ProjectPatch {
+    pub fn is_empty(&self) -> bool {
+        self.name.is_none()
+            && self.customer_name.is_none()
+            && self.customer_document.is_none()
+            && self.customer_contact.is_none()
+    }
+
+    pub fn apply(&self, project: &mut Project) {
+        if let Some(name) = &self.name {
+            project.name = name.clone();
+        }
+        if let Some(customer_name) = &self.customer_name {
+            project.customer_name = customer_name.clone();
+        }
+        if let Some(customer_document) = &self.customer_document {
+            project.customer_document = customer_document.clone();
+        }
+        if let Some(customer_contact) = &self.customer_contact {
+            project.customer_contact = customer_contact.clone();
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use chrono::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::client::Client;
+use crate::error::{Error, Result};
+use crate::models::{
+    unsynced_id, Comment, NewComment, NewProject, NewTask, Project, ProjectPatch, Task, TaskPatch,
+    TaskStatus,
+};
+use crate::store::Store;
+
+/// A local change waiting to be sent to the server.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Mutation {
+    CreateTask { task: NewTask },
+    CompleteTask { id: usize },
+    ReopenTask { id: usize },
+    DeleteTask { id: usize },
+    UpdateTask { id: usize, patch: TaskPatch },
+    CreateComment { comment: NewComment },
+    CreateProject { project: NewProject },
+    UpdateProject { id: usize, patch: ProjectPatch },
+}
+
+impl Mutation {
+    pub async fn send(&self, client: &Client) -> Result<()> {
+        match self {
+            Mutation::CreateTask { task } => client.create_task(task).await.map(|_| ()),
+            Mutation::CompleteTask { id } => client.complete_task(*id).await,
+            Mutation::ReopenTask { id } => client.reopen_task(*id).await,
+            Mutation::DeleteTask { id } => client.delete_task(*id).await,
+            Mutation::UpdateTask { id, patch } => client.update_task(*id, patch).await.map(|_| ()),
+            Mutation::CreateComment { comment } => client.create_comment(comment).await.map(|_| ()),
+            Mutation::CreateProject { project } => client.create_project(project).await.map(|_| ()),
+            Mutation::UpdateProject { id, patch } => client.update_project(*id, patch).await.map(|_| ()),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct OutboxEntry {
+    pub id: u64,
+    pub mutation: Mutation,
+    pub created_at: DateTime<Utc>,
+    /// Why the server rejected it; failed entries wait for a retry or discard.
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+impl OutboxEntry {
+    pub fn is_pending(&self) -> bool {
+        self.error.is_none()
+    }
+
+    /// Applies the change to cached records so the UI reflects it before the server does.
+    pub fn apply(&self, tasks: &mut Vec<Task>, comments: &mut Vec<Comment>, projects: &mut Vec<Project>) {
+        match &self.mutation {
+            // The server assigns the id, the next sync brings it in.
+            Mutation::CreateTask { task } => tasks.push(Task {
+                id: unsynced_id(self.id),
+                project: task.project.clone(),
+                content_preview: task.content_preview.clone(),
+                content: task.content.clone(),
+                begin_date: task.begin_date,
+                end_date: task.end_date,
+                finish_date: None,
+                status: Some(TaskStatus::Pending),
+            }),
+            Mutation::CompleteTask { id } => {
+                if let Some(task) = tasks.iter_mut().find(|task| task.id == *id) {
+                    task.finish_date = Some(self.created_at);
+                    task.status = Some(TaskStatus::Done);
+                }
+            }
+            Mutation::ReopenTask { id } => {
+                if let Some(task) = tasks.iter_mut().find(|task| task.id == *id) {
+                    task.finish_date = None;
+                    task.status = Some(TaskStatus::Pending);
+                }
+            }
+            Mutation::DeleteTask { id } => tasks.retain(|task| task.id != *id),
+            Mutation::UpdateTask { id, patch } => {
+                if let Some(task) = tasks.iter_mut().find(|task| task.id == *id) {
+                    patch.apply(task);
+                }
+            }
+            Mutation::CreateComment { comment } => comments.push(Comment {
+                id: unsynced_id(self.id),
+                task_id: Some(comment.task_id),
+                task_preview: comment.task_preview.clone(),
+                content: comment.content.clone(),
+                created_at: self.created_at,
+            }),
+            Mutation::CreateProject { project } => projects.push(Project {
+                id: unsynced_id(self.id),
+                name: project.name.clone(),
+                customer_name: project.customer_name.clone(),
+                customer_document: project.customer_document.clone(),
+                customer_contact: project.customer_contact.clone(),
+                created_at: self.created_at,
+            }),
+            Mutation::UpdateProject { id, patch } => {
+                if let Some(project) = projects.iter_mut().find(|project| project.id == *id) {
+                    patch.apply(project);
+                }
+            }
+        }
+    }
+}
+
+/// Durable, ordered queue of local changes, kept in the local store.
+///
+/// Changes go to the store entry by entry as well as to memory, see `Store::outbox`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Outbox {
+    pub entries: Vec<OutboxEntry>,
+}
+
+impl Outbox {
+    pub fn load(store: &dyn Store) -> Result<Outbox> {
+        Ok(Outbox {
+            entries: store.outbox()?,
+        })
+    }
+
+    pub fn push(&mut self, store: &mut dyn Store, mutation: Mutation) -> Result<u64> {
+        let mut entry = OutboxEntry {
+            id: 0,
+            mutation,
+            created_at: Utc::now(),
+            error: None,
+        };
+        entry.id = store.push_outbox(&entry)?;
+        let id = entry.id;
+        self.entries.push(entry);
+        Ok(id)
+    }
+
+    pub fn remove(&mut self, store: &mut dyn Store, id: u64) -> Result<()> {
+        store.remove_outbox(id)?;
+        self.entries.retain(|entry| entry.id != id);
+        Ok(())
+    }
+
+    /// Puts a failed entry back in the queue.
+    pub fn retry(&mut self, store: &mut dyn Store, id: u64) -> Result<()> {
+        store.set_outbox_error(id, None)?;
+        if let Some(entry) = self.entries.iter_mut().find(|entry| entry.id == id) {
+            entry.error = None;
+        }
+        Ok(())
+    }
+
+    pub fn pending(&self) -> Vec<OutboxEntry> {
+        self.entries.iter().filter(|entry| entry.is_pending()).cloned().collect()
+    }
+
+    /// Removes the entries the server accepted and records why the others were rejected.
+    pub fn apply_replay(&mut self, store: &mut dyn Store, replay: &Replay) -> Result<()> {
+        for id in &replay.applied {
+            self.remove(store, *id)?;
+        }
+        for (id, e) in &replay.rejected {
+            store.set_outbox_error(*id, Some(e))?;
+            if let Some(entry) = self.entries.iter_mut().find(|entry| entry.id == *id) {
+                entry.error = Some(e.clone());
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Outcome of sending queued entries to the server.
+#[derive(Debug, Default)]
+pub struct Replay {
+    pub applied: Vec<u64>,
+    pub rejected: Vec<(u64, String)>,
+    /// Set when the server could not be reached; the remaining entries stay queued.
+    pub interrupted: Option<Error>,
+}
+
+/// Sends `entries` in order, stopping at the first connectivity failure.
+pub async fn replay(client: &Client, entries: &[OutboxEntry]) -> Replay {
+    let mut replay = Replay::default();
+    for entry in entries {
+        match entry.mutation.send(client).await {
+            Ok(()) => replay.applied.push(entry.id),
+            Err(e) if e.is_transient() => {
+                replay.interrupted = Some(e);
+                break;
+            }
+            Err(e) => replay.rejected.push((entry.id, e.to_string())),
+        }
+    }
+    replay
+}
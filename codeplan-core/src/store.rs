@@ -0,0 +1,489 @@
+use std::fs;
+use std::path::Path;
+
+use rusqlite::{params, Connection, OptionalExtension, ToSql};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::cache::{self, COMMENT_PATH, OUTBOX_PATH, PROJECT_PATH, TASK_PATH};
+use crate::error::Result;
+use crate::models::{Comment, Project, Task};
+use crate::outbox::{Outbox, OutboxEntry};
+
+/// Name of the database in the server's data directory, see `Settings::db_path`.
+pub const DB_FILE: &str = "codeplan.db";
+
+/// Local persistence for synced records, sync metadata and the outbox.
+pub trait Store: Send {
+    fn tasks(&self) -> Result<Vec<Task>>;
+    fn comments(&self) -> Result<Vec<Comment>>;
+    fn projects(&self) -> Result<Vec<Project>>;
+
+    /// Replaces every cached task in a single transaction.
+    fn replace_tasks(&mut self, tasks: &[Task]) -> Result<()>;
+    fn replace_comments(&mut self, comments: &[Comment]) -> Result<()>;
+    fn replace_projects(&mut self, projects: &[Project]) -> Result<()>;
+
+    /// Inserts or updates `tasks` and removes the `deleted` ids, in a single transaction.
+    fn merge_tasks(&mut self, tasks: &[Task], deleted: &[usize]) -> Result<()>;
+    fn merge_comments(&mut self, comments: &[Comment], deleted: &[usize]) -> Result<()>;
+    fn merge_projects(&mut self, projects: &[Project], deleted: &[usize]) -> Result<()>;
+
+    /// Every change to the outbox touches a single entry, so processes sharing the
+    /// store never drop or bring back each other's entries.
+    fn outbox(&self) -> Result<Vec<OutboxEntry>>;
+    /// Appends `entry`, returning the id the store gave it; ids are never reused.
+    fn push_outbox(&mut self, entry: &OutboxEntry) -> Result<u64>;
+    /// Records why the server rejected an entry, or clears it with `None` for a retry.
+    fn set_outbox_error(&mut self, id: u64, error: Option<&str>) -> Result<()>;
+    fn remove_outbox(&mut self, id: u64) -> Result<()>;
+
+    fn sync_meta(&self, key: &str) -> Result<Option<String>>;
+    fn set_sync_meta(&mut self, key: &str, value: &str) -> Result<()>;
+
+    /// UI choices kept between sessions, like the active filters.
+    fn preference(&self, key: &str) -> Result<Option<String>>;
+    /// Stores `value` for `key`, or forgets it when `None`.
+    fn set_preference(&mut self, key: &str, value: Option<&str>) -> Result<()>;
+
+    /// Changes whenever another connection commits, so readers know when to reload.
+    fn revision(&self) -> Result<i64>;
+}
+
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE tasks (
+        id INTEGER PRIMARY KEY,
+        position INTEGER NOT NULL,
+        project TEXT NOT NULL,
+        end_date TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX tasks_project ON tasks (project);
+    CREATE INDEX tasks_end_date ON tasks (end_date);
+    CREATE TABLE comments (
+        id INTEGER PRIMARY KEY,
+        position INTEGER NOT NULL,
+        task_preview TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE INDEX comments_task_preview ON comments (task_preview);
+    CREATE TABLE projects (
+        id INTEGER PRIMARY KEY,
+        position INTEGER NOT NULL,
+        name TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    CREATE TABLE sync_meta (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );
+    CREATE TABLE outbox (
+        id INTEGER PRIMARY KEY,
+        data TEXT NOT NULL
+    );",
+    "CREATE TABLE preferences (
+        key TEXT PRIMARY KEY,
+        value TEXT NOT NULL
+    );",
+    "CREATE TABLE outbox_entries (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        data TEXT NOT NULL
+    );
+    INSERT INTO outbox_entries (id, data) SELECT id, data FROM outbox;
+    DROP TABLE outbox;
+    ALTER TABLE outbox_entries RENAME TO outbox;",
+    // The project filter works on the tasks in memory, which include queued ones.
+    "CREATE TABLE tasks_by_end_date (
+        id INTEGER PRIMARY KEY,
+        position INTEGER NOT NULL,
+        end_date TEXT NOT NULL,
+        data TEXT NOT NULL
+    );
+    INSERT INTO tasks_by_end_date (id, position, end_date, data)
+        SELECT id, position, end_date, data FROM tasks;
+    DROP TABLE tasks;
+    ALTER TABLE tasks_by_end_date RENAME TO tasks;
+    CREATE INDEX tasks_end_date ON tasks (end_date);",
+];
+
+pub struct SqliteStore {
+    conn: Connection,
+}
+
+impl SqliteStore {
+    pub fn open(path: &Path) -> Result<SqliteStore> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let conn = Connection::open(path)?;
+        conn.execute_batch("PRAGMA journal_mode = WAL; PRAGMA busy_timeout = 5000;")?;
+        let mut store = SqliteStore { conn };
+        if store.migrate()? == 0 {
+            store.import_legacy_cache()?;
+        }
+        Ok(store)
+    }
+
+    /// An empty store that lives only as long as the process, for when `open` fails.
+    pub fn open_in_memory() -> Result<SqliteStore> {
+        let mut store = SqliteStore {
+            conn: Connection::open_in_memory()?,
+        };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    /// Applies pending schema migrations, returning the version the database was at.
+    fn migrate(&mut self) -> Result<usize> {
+        let version: i64 = self
+            .conn
+            .query_row("PRAGMA user_version", params![], |row| row.get(0))?;
+        let version = version as usize;
+        for (index, migration) in MIGRATIONS.iter().enumerate().skip(version) {
+            let tx = self.conn.transaction()?;
+            tx.execute_batch(migration)?;
+            tx.execute_batch(&format!("PRAGMA user_version = {}", index + 1))?;
+            tx.commit()?;
+        }
+        Ok(version)
+    }
+
+    /// Brings over the JSON files written by earlier versions, if they are still around.
+    fn import_legacy_cache(&mut self) -> Result<()> {
+        if let Ok(tasks) = cache::read::<Task>(TASK_PATH) {
+            self.replace_tasks(&tasks)?;
+        }
+        if let Ok(comments) = cache::read::<Comment>(COMMENT_PATH) {
+            self.replace_comments(&comments)?;
+        }
+        if let Ok(projects) = cache::read::<Project>(PROJECT_PATH) {
+            self.replace_projects(&projects)?;
+        }
+        if let Ok(content) = fs::read_to_string(OUTBOX_PATH) {
+            if let Ok(outbox) = serde_json::from_str::<Outbox>(&content) {
+                for entry in &outbox.entries {
+                    self.push_outbox(entry)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs a query selecting the `data` column and deserializes each row.
+    fn query<T: DeserializeOwned>(&self, sql: &str, args: &[&dyn ToSql]) -> Result<Vec<T>> {
+        let mut stmt = self.conn.prepare(sql)?;
+        let rows = stmt.query_map(args, |row| row.get::<_, String>(0))?;
+        let mut items = Vec::new();
+        for row in rows {
+            items.push(serde_json::from_str(&row?)?);
+        }
+        Ok(items)
+    }
+
+    /// Replaces the content of `table`. `columns` names its indexed columns, and
+    /// `row` gives the id and those columns' values of each item.
+    fn replace<T, F>(&mut self, table: &str, columns: &[&str], items: &[T], row: F) -> Result<()>
+    where
+        T: Serialize,
+        F: Fn(&T) -> (usize, Vec<String>),
+    {
+        let tx = self.conn.transaction()?;
+        tx.execute(&format!("DELETE FROM {}", table), params![])?;
+        {
+            let mut stmt = tx.prepare(&format!(
+                "INSERT OR REPLACE INTO {} (id, position, {}, data) VALUES (?1, ?2, {})",
+                table,
+                columns.join(", "),
+                placeholders(3, columns.len() + 1)
+            ))?;
+            for (position, item) in items.iter().enumerate() {
+                let (id, values) = row(item);
+                let (id, position, data) = (id as i64, position as i64, serde_json::to_string(item)?);
+                let mut args: Vec<&dyn ToSql> = vec![&id, &position];
+                args.extend(values.iter().map(|value| value as &dyn ToSql));
+                args.push(&data);
+                stmt.execute(&args[..])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Upserts `items` into `table`, keeping the position of rows that already exist.
+    fn merge<T, F>(&mut self, table: &str, columns: &[&str], items: &[T], deleted: &[usize], row: F) -> Result<()>
+    where
+        T: Serialize,
+        F: Fn(&T) -> (usize, Vec<String>),
+    {
+        let tx = self.conn.transaction()?;
+        {
+            let mut upsert = tx.prepare(&format!(
+                "INSERT OR REPLACE INTO {table} (id, position, {columns}, data) VALUES (?1,
+                    COALESCE((SELECT position FROM {table} WHERE id = ?1),
+                             (SELECT IFNULL(MAX(position), -1) + 1 FROM {table})),
+                    {values})",
+                table = table,
+                columns = columns.join(", "),
+                values = placeholders(2, columns.len() + 1)
+            ))?;
+            for item in items {
+                let (id, values) = row(item);
+                let (id, data) = (id as i64, serde_json::to_string(item)?);
+                let mut args: Vec<&dyn ToSql> = vec![&id];
+                args.extend(values.iter().map(|value| value as &dyn ToSql));
+                args.push(&data);
+                upsert.execute(&args[..])?;
+            }
+            let mut delete = tx.prepare(&format!("DELETE FROM {} WHERE id = ?1", table))?;
+            for id in deleted {
+                delete.execute(params![*id as i64])?;
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+/// `count` numbered SQL parameters starting at `?first`.
+fn placeholders(first: usize, count: usize) -> String {
+    (first..first + count)
+        .map(|index| format!("?{}", index))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+impl Store for SqliteStore {
+    fn tasks(&self) -> Result<Vec<Task>> {
+        self.query("SELECT data FROM tasks ORDER BY position", &[])
+    }
+
+    fn comments(&self) -> Result<Vec<Comment>> {
+        self.query("SELECT data FROM comments ORDER BY position", &[])
+    }
+
+    fn projects(&self) -> Result<Vec<Project>> {
+        self.query("SELECT data FROM projects ORDER BY position", &[])
+    }
+
+    fn replace_tasks(&mut self, tasks: &[Task]) -> Result<()> {
+        self.replace("tasks", &["end_date"], tasks, |task| {
+            (task.id, vec![task.end_date.to_rfc3339()])
+        })
+    }
+
+    fn replace_comments(&mut self, comments: &[Comment]) -> Result<()> {
+        self.replace("comments", &["task_preview"], comments, |comment| {
+            (comment.id, vec![comment.task_preview.clone()])
+        })
+    }
+
+    fn replace_projects(&mut self, projects: &[Project]) -> Result<()> {
+        self.replace("projects", &["name"], projects, |project| {
+            (project.id, vec![project.name.clone()])
+        })
+    }
+
+    fn merge_tasks(&mut self, tasks: &[Task], deleted: &[usize]) -> Result<()> {
+        self.merge("tasks", &["end_date"], tasks, deleted, |task| {
+            (task.id, vec![task.end_date.to_rfc3339()])
+        })
+    }
+
+    fn merge_comments(&mut self, comments: &[Comment], deleted: &[usize]) -> Result<()> {
+        self.merge("comments", &["task_preview"], comments, deleted, |comment| {
+            (comment.id, vec![comment.task_preview.clone()])
+        })
+    }
+
+    fn merge_projects(&mut self, projects: &[Project], deleted: &[usize]) -> Result<()> {
+        self.merge("projects", &["name"], projects, deleted, |project| {
+            (project.id, vec![project.name.clone()])
+        })
+    }
+
+    fn outbox(&self) -> Result<Vec<OutboxEntry>> {
+        let mut stmt = self.conn.prepare("SELECT id, data FROM outbox ORDER BY id")?;
+        let rows = stmt.query_map(params![], |row| {
+            Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut entries = Vec::new();
+        for row in rows {
+            let (id, data) = row?;
+            let mut entry: OutboxEntry = serde_json::from_str(&data)?;
+            // `data` holds whatever id the entry had before the store gave it one.
+            entry.id = id as u64;
+            entries.push(entry);
+        }
+        Ok(entries)
+    }
+
+    fn push_outbox(&mut self, entry: &OutboxEntry) -> Result<u64> {
+        self.conn.execute(
+            "INSERT INTO outbox (data) VALUES (?1)",
+            params![serde_json::to_string(entry)?],
+        )?;
+        Ok(self.conn.last_insert_rowid() as u64)
+    }
+
+    fn set_outbox_error(&mut self, id: u64, error: Option<&str>) -> Result<()> {
+        let tx = self.conn.transaction()?;
+        let data: Option<String> = tx
+            .query_row(
+                "SELECT data FROM outbox WHERE id = ?1",
+                params![id as i64],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if let Some(data) = data {
+            let mut entry: OutboxEntry = serde_json::from_str(&data)?;
+            entry.error = error.map(String::from);
+            tx.execute(
+                "UPDATE outbox SET data = ?2 WHERE id = ?1",
+                params![id as i64, serde_json::to_string(&entry)?],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn remove_outbox(&mut self, id: u64) -> Result<()> {
+        self.conn
+            .execute("DELETE FROM outbox WHERE id = ?1", params![id as i64])?;
+        Ok(())
+    }
+
+    fn sync_meta(&self, key: &str) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT value FROM sync_meta WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn set_sync_meta(&mut self, key: &str, value: &str) -> Result<()> {
+        self.conn.execute(
+            "INSERT OR REPLACE INTO sync_meta (key, value) VALUES (?1, ?2)",
+            params![key, value],
+        )?;
+        Ok(())
+    }
+
+    fn preference(&self, key: &str) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row(
+                "SELECT value FROM preferences WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?)
+    }
+
+    fn set_preference(&mut self, key: &str, value: Option<&str>) -> Result<()> {
+        match value {
+            Some(value) => self.conn.execute(
+                "INSERT OR REPLACE INTO preferences (key, value) VALUES (?1, ?2)",
+                params![key, value],
+            )?,
+            None => self
+                .conn
+                .execute("DELETE FROM preferences WHERE key = ?1", params![key])?,
+        };
+        Ok(())
+    }
+
+    fn revision(&self) -> Result<i64> {
+        Ok(self
+            .conn
+            .query_row("PRAGMA data_version", params![], |row| row.get(0))?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::prelude::*;
+
+    use super::*;
+    use crate::outbox::Mutation;
+
+    fn comment(id: usize, content: &str) -> Comment {
+        Comment {
+            id,
+            task_id: Some(1),
+            task_preview: String::from("Task"),
+            content: content.to_owned(),
+            created_at: Utc.ymd(2024, 5, 1).and_hms(12, 0, 0),
+        }
+    }
+
+    fn contents(store: &SqliteStore) -> Vec<(usize, String)> {
+        store
+            .comments()
+            .unwrap()
+            .into_iter()
+            .map(|comment| (comment.id, comment.content))
+            .collect()
+    }
+
+    #[test]
+    fn merge_upserts_in_place_and_removes_deleted() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        store
+            .replace_comments(&[comment(1, "a"), comment(2, "b"), comment(3, "c")])
+            .unwrap();
+        store
+            .merge_comments(&[comment(4, "d"), comment(1, "a2")], &[2, 99])
+            .unwrap();
+        assert_eq!(
+            contents(&store),
+            vec![(1, String::from("a2")), (3, String::from("c")), (4, String::from("d"))]
+        );
+    }
+
+    #[test]
+    fn replace_drops_what_is_not_listed() {
+        let mut store = SqliteStore::open_in_memory().unwrap();
+        store.replace_comments(&[comment(1, "a"), comment(2, "b")]).unwrap();
+        store.replace_comments(&[comment(2, "b2")]).unwrap();
+        assert_eq!(contents(&store), vec![(2, String::from("b2"))]);
+    }
+
+    #[test]
+    fn outbox_migration_keeps_entries_and_their_ids() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.execute_batch(MIGRATIONS[1]).unwrap();
+        conn.execute_batch("PRAGMA user_version = 2").unwrap();
+        let entry = OutboxEntry {
+            id: 5,
+            mutation: Mutation::CompleteTask { id: 42 },
+            created_at: Utc::now(),
+            error: Some(String::from("rejected")),
+        };
+        conn.execute(
+            "INSERT INTO outbox (id, data) VALUES (?1, ?2)",
+            params![5, serde_json::to_string(&entry).unwrap()],
+        )
+        .unwrap();
+
+        let mut store = SqliteStore { conn };
+        assert_eq!(store.migrate().unwrap(), 2);
+        let entries = store.outbox().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].id, 5);
+        assert_eq!(entries[0].error.as_deref(), Some("rejected"));
+        match entries[0].mutation {
+            Mutation::CompleteTask { id } => assert_eq!(id, 42),
+            ref other => panic!("expected CompleteTask, got {:?}", other),
+        }
+
+        // Ids keep counting from the copied entries and are not reused after a removal.
+        let pushed = store.push_outbox(&entry).unwrap();
+        assert_eq!(pushed, 6);
+        store.remove_outbox(pushed).unwrap();
+        assert_eq!(store.push_outbox(&entry).unwrap(), 7);
+    }
+}
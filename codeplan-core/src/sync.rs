@@ -0,0 +1,165 @@
+use std::fmt;
+
+use chrono::prelude::*;
+
+use crate::client::{Changes, Client, Cursor};
+use crate::error::Error;
+use crate::models::{Comment, Project, Task};
+use crate::outbox::{self, OutboxEntry, Replay};
+use crate::store::Store;
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Resource {
+    Tasks,
+    Comments,
+    Projects,
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Resource::Tasks => write!(f, "tasks"),
+            Resource::Comments => write!(f, "comments"),
+            Resource::Projects => write!(f, "projects"),
+        }
+    }
+}
+
+/// Progress notifications sent while a sync runs.
+#[derive(Debug)]
+pub enum SyncEvent {
+    Replaying(usize),
+    Started(Resource),
+    Finished(Resource, usize),
+    Failed(Resource, String),
+}
+
+/// Outcome of a full sync; a resource that failed is `None` and listed in `failures`.
+#[derive(Debug, Default)]
+pub struct SyncReport {
+    pub replay: Replay,
+    pub tasks: Option<Vec<Task>>,
+    pub comments: Option<Vec<Comment>>,
+    pub projects: Option<Vec<Project>>,
+    pub failures: Vec<(Resource, Error)>,
+}
+
+impl SyncReport {
+    pub fn is_success(&self) -> bool {
+        self.failures.is_empty() && self.replay.interrupted.is_none()
+    }
+}
+
+/// Replays the queued `outbox` entries, then downloads every resource into `store`.
+///
+/// A failing resource doesn't stop the others from syncing.
+pub async fn sync_all<F>(
+    client: &Client,
+    store: &mut dyn Store,
+    outbox: &[OutboxEntry],
+    mut progress: F,
+) -> SyncReport
+where
+    F: FnMut(SyncEvent),
+{
+    let mut report = SyncReport::default();
+
+    if !outbox.is_empty() {
+        progress(SyncEvent::Replaying(outbox.len()));
+        report.replay = outbox::replay(client, outbox).await;
+    }
+
+    progress(SyncEvent::Started(Resource::Tasks));
+    match sync_tasks(client, store).await {
+        Ok(tasks) => {
+            progress(SyncEvent::Finished(Resource::Tasks, tasks.len()));
+            report.tasks = Some(tasks);
+        }
+        Err(e) => {
+            progress(SyncEvent::Failed(Resource::Tasks, e.to_string()));
+            report.failures.push((Resource::Tasks, e));
+        }
+    }
+
+    progress(SyncEvent::Started(Resource::Comments));
+    match sync_comments(client, store).await {
+        Ok(comments) => {
+            progress(SyncEvent::Finished(Resource::Comments, comments.len()));
+            report.comments = Some(comments);
+        }
+        Err(e) => {
+            progress(SyncEvent::Failed(Resource::Comments, e.to_string()));
+            report.failures.push((Resource::Comments, e));
+        }
+    }
+
+    progress(SyncEvent::Started(Resource::Projects));
+    match sync_projects(client, store).await {
+        Ok(projects) => {
+            progress(SyncEvent::Finished(Resource::Projects, projects.len()));
+            report.projects = Some(projects);
+        }
+        Err(e) => {
+            progress(SyncEvent::Failed(Resource::Projects, e.to_string()));
+            report.failures.push((Resource::Projects, e));
+        }
+    }
+
+    report
+}
+
+async fn sync_tasks(client: &Client, store: &mut dyn Store) -> Result<Vec<Task>, Error> {
+    let fetched = client.list_tasks_since(&load_cursor(store, Resource::Tasks)).await?;
+    match &fetched.changes {
+        Changes::NotModified => {}
+        Changes::Full(tasks) => store.replace_tasks(tasks)?,
+        Changes::Delta { items, deleted } => store.merge_tasks(items, deleted)?,
+    }
+    save_cursor(store, Resource::Tasks, &fetched.cursor)?;
+    store.tasks()
+}
+
+async fn sync_comments(client: &Client, store: &mut dyn Store) -> Result<Vec<Comment>, Error> {
+    let fetched = client.list_comments_since(&load_cursor(store, Resource::Comments)).await?;
+    match &fetched.changes {
+        Changes::NotModified => {}
+        Changes::Full(comments) => store.replace_comments(comments)?,
+        Changes::Delta { items, deleted } => store.merge_comments(items, deleted)?,
+    }
+    save_cursor(store, Resource::Comments, &fetched.cursor)?;
+    store.comments()
+}
+
+async fn sync_projects(client: &Client, store: &mut dyn Store) -> Result<Vec<Project>, Error> {
+    let fetched = client.list_projects_since(&load_cursor(store, Resource::Projects)).await?;
+    match &fetched.changes {
+        Changes::NotModified => {}
+        Changes::Full(projects) => store.replace_projects(projects)?,
+        Changes::Delta { items, deleted } => store.merge_projects(items, deleted)?,
+    }
+    save_cursor(store, Resource::Projects, &fetched.cursor)?;
+    store.projects()
+}
+
+/// A missing or unreadable cursor means starting over with a full fetch.
+fn load_cursor(store: &dyn Store, resource: Resource) -> Cursor {
+    store
+        .sync_meta(&format!("{}.cursor", resource))
+        .ok()
+        .flatten()
+        .and_then(|cursor| serde_json::from_str(&cursor).ok())
+        .unwrap_or_default()
+}
+
+fn save_cursor(store: &mut dyn Store, resource: Resource, cursor: &Cursor) -> Result<(), Error> {
+    store.set_sync_meta(&format!("{}.cursor", resource), &serde_json::to_string(cursor)?)?;
+    store.set_sync_meta(&format!("{}.synced_at", resource), &Utc::now().to_rfc3339())
+}
+
+/// Forgets every cursor so the next sync downloads everything again.
+pub fn reset_cursors(store: &mut dyn Store) -> Result<(), Error> {
+    for resource in &[Resource::Tasks, Resource::Comments, Resource::Projects] {
+        store.set_sync_meta(&format!("{}.cursor", resource), "")?;
+    }
+    Ok(())
+}
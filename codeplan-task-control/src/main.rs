@@ -1,59 +1,54 @@
 use std::env;
-use std::io::{Read, Write};
-use std::io::prelude::*;
 
-use reqwest::Client;
+use codeplan_core::client::Client;
+use codeplan_core::config::{flag_value, Settings};
 
 type Error = Box<dyn std::error::Error>;
 type Result<T, E = Error> = std::result::Result<T, E>;
 
-async fn complete_task(task_id: &str) -> Result<()> {
-    let mut url = "http://172.30.152.201:4000/tasks/".to_owned();
-    url.push_str(task_id);
-    url.push_str("/");
-    url.push_str("complete");
-    let client = Client::new();
-    let req = client
-        // or use .post, etc.
-        .post(&url)
-        .header("Accepts", "application/json");
-    let res = req.send().await?;
-    let body = res.bytes().await?;
-    let v = body.to_vec();
-    let s = String::from_utf8_lossy(&v);
-    let body_json = String::from(s);
+/// Completes the task and prints the server's copy of it as JSON on stdout.
+async fn complete_task(client: &Client, task_id: &str) -> Result<()> {
+    let id = task_id.parse()?;
+    client.complete_task(id).await?;
+    let task = client.get_task(id).await?;
+    println!("{}", serde_json::to_string(&task)?);
     Ok(())
 }
 
-async fn delete_task(task_id: &str) -> Result<()> {
-    let mut url = "http://172.30.152.201:4000/tasks/".to_owned();
-    url.push_str(task_id);
-    let client = Client::new();
-    let req = client
-        // or use .post, etc.
-        .delete(&url)
-        .header("Accepts", "application/json");
-    let res = req.send().await?;
-    let body = res.bytes().await?;
-    let v = body.to_vec();
-    let s = String::from_utf8_lossy(&v);
-    let body_json = String::from(s);
+/// Reopens a completed task and prints the server's copy of it as JSON on stdout.
+async fn reopen_task(client: &Client, task_id: &str) -> Result<()> {
+    let id = task_id.parse()?;
+    client.reopen_task(id).await?;
+    let task = client.get_task(id).await?;
+    println!("{}", serde_json::to_string(&task)?);
+    Ok(())
+}
+
+async fn delete_task(client: &Client, task_id: &str) -> Result<()> {
+    client.delete_task(task_id.parse()?).await?;
     Ok(())
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args: Vec<String> = env::args().collect();
+    let settings = Settings::from_args(&args)?;
+    let client = Client::from_settings(&settings)?;
     if args.contains(&String::from("-complete")) {
-        if args.len() > 2 {
-            let task_index: usize = args.iter().position(|r| r == "-complete").unwrap() + 1;
-            complete_task(&args[task_index]).await?;
-        } else { println!("Missing or incorrect arguments.") }
+        match flag_value(&args, "-complete") {
+            Some(task_id) => complete_task(&client, task_id).await?,
+            None => println!("Missing or incorrect arguments."),
+        }
+    } else if args.contains(&String::from("-reopen")) {
+        match flag_value(&args, "-reopen") {
+            Some(task_id) => reopen_task(&client, task_id).await?,
+            None => println!("Missing or incorrect arguments."),
+        }
     } else if args.contains(&String::from("-delete")) {
-        if args.len() > 2 {
-            let task_index: usize = args.iter().position(|r| r == "-delete").unwrap() + 1;
-            delete_task(&args[task_index]).await?;
-        } else { println!("Missing or incorrect arguments.") }
+        match flag_value(&args, "-delete") {
+            Some(task_id) => delete_task(&client, task_id).await?,
+            None => println!("Missing or incorrect arguments."),
+        }
     }
     Ok(())
-}
\ No newline at end of file
+}
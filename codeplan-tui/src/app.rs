@@ -0,0 +1,705 @@
+use std::path::PathBuf;
+
+use codeplan_core::config::DEFAULT_DUE_SOON_DAYS;
+use codeplan_core::files::{StoreLock, LOCK_FILE};
+use codeplan_core::models::{is_unsynced, TaskStatus};
+use codeplan_core::outbox::{Mutation, Outbox};
+use codeplan_core::store::{Store, DB_FILE};
+use codeplan_core::sync::{Resource, SyncEvent, SyncReport};
+use codeplan_core::{Comment, Error, Project, Task};
+use tui::widgets::ListState;
+
+use crate::calendar::Calendar;
+use crate::modal::Modal;
+use crate::search::{Search, SearchScope};
+
+#[derive(Copy, Clone, Debug)]
+pub enum MenuItem {
+    Home,
+    Monitor,
+    Comments,
+    Projects,
+    Outbox,
+    Calendar,
+    License,
+    Error,
+}
+
+impl<'a> From<MenuItem> for usize {
+    fn from(input: MenuItem) -> usize {
+        match input {
+            MenuItem::Home => 0,
+            MenuItem::Monitor => 1,
+            MenuItem::Comments => 2,
+            MenuItem::Projects => 3,
+            MenuItem::Outbox => 4,
+            MenuItem::Calendar => 5,
+            MenuItem::License => 6,
+            MenuItem::Error => 7,
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum StatusKind {
+    Info,
+    Success,
+    Failure,
+}
+
+/// Message shown in the status bar below the options.
+pub struct Status {
+    pub kind: StatusKind,
+    pub message: String,
+}
+
+/// Details of the last failure, shown on the Error screen.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Failure {
+    /// What the app was doing, e.g. "Carregar dados locais".
+    pub action: String,
+    pub cause: String,
+    /// File or endpoint involved, if known.
+    pub location: Option<String>,
+    pub hint: &'static str,
+}
+
+impl Failure {
+    pub fn new(action: &str, error: &Error) -> Failure {
+        Failure {
+            action: action.to_owned(),
+            cause: error.to_string(),
+            location: error.location(),
+            hint: hint(error),
+        }
+    }
+}
+
+/// Suggested fix for each kind of error.
+fn hint(error: &Error) -> &'static str {
+    match error {
+        Error::ReadDBError(_) => "Verifique se o arquivo existe e se há permissão de leitura e escrita.",
+        Error::ParseDBError(_) => "O arquivo está corrompido: apague-o e sincronize novamente (u).",
+        Error::StoreError(_) => "Feche outras instâncias do codeplan ou apague o banco local e sincronize novamente (u).",
+        Error::RequestError(e) if e.is_connect() || e.is_timeout() => {
+            "Verifique a conexão e o endereço do servidor (--server ou CODEPLAN_SERVER)."
+        }
+        Error::RequestError(_) => "O servidor enviou uma resposta inesperada, verifique se a versão é compatível.",
+        Error::StatusError { status, .. } if status.is_server_error() => {
+            "O servidor está com problemas, tente novamente mais tarde (u)."
+        }
+        Error::StatusError { status, .. } if status.as_u16() == 404 => {
+            "Verifique o endereço do servidor e o perfil em uso (--profile)."
+        }
+        Error::StatusError { .. } => "Confira a operação na aba Operações e tente novamente.",
+        Error::ValidationError { .. } => "Corrija os campos indicados e tente novamente.",
+        Error::AuthError { .. } => "Confira client_id e client_secret em client.json.",
+        Error::ConfigError(_) => "Corrija o arquivo de configuração.",
+        Error::UnknownProfileError(_) => "Adicione o perfil ao arquivo de configuração ou use --profile com um perfil existente.",
+    }
+}
+
+/// Preference key of the Monitor's project filter.
+const PROJECT_FILTER_KEY: &str = "monitor.project";
+
+/// Preference key of the Monitor's sort, e.g. "end_date" or "end_date desc".
+const SORT_KEY: &str = "monitor.sort";
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SortField {
+    EndDate,
+    BeginDate,
+    Project,
+    Id,
+    Preview,
+}
+
+impl SortField {
+    const ALL: [SortField; 5] = [
+        SortField::EndDate,
+        SortField::BeginDate,
+        SortField::Project,
+        SortField::Id,
+        SortField::Preview,
+    ];
+
+    pub fn next(self) -> SortField {
+        let index = SortField::ALL.iter().position(|field| *field == self).unwrap_or(0);
+        SortField::ALL[(index + 1) % SortField::ALL.len()]
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SortField::EndDate => "Entrega",
+            SortField::BeginDate => "Início",
+            SortField::Project => "Projeto",
+            SortField::Id => "Id",
+            SortField::Preview => "Título",
+        }
+    }
+
+    fn key(self) -> &'static str {
+        match self {
+            SortField::EndDate => "end_date",
+            SortField::BeginDate => "begin_date",
+            SortField::Project => "project",
+            SortField::Id => "id",
+            SortField::Preview => "preview",
+        }
+    }
+}
+
+/// Order of the Monitor list.
+#[derive(Copy, Clone, Debug)]
+pub struct TaskSort {
+    pub field: SortField,
+    pub descending: bool,
+}
+
+impl Default for TaskSort {
+    fn default() -> TaskSort {
+        TaskSort {
+            field: SortField::EndDate,
+            descending: false,
+        }
+    }
+}
+
+impl TaskSort {
+    /// Sorts by the field, then by id so equal values keep a stable order.
+    pub fn sort(&self, tasks: &mut [Task]) {
+        tasks.sort_by(|a, b| {
+            let order = match self.field {
+                SortField::EndDate => a.end_date.cmp(&b.end_date),
+                SortField::BeginDate => a.begin_date.cmp(&b.begin_date),
+                SortField::Project => a.project.to_lowercase().cmp(&b.project.to_lowercase()),
+                SortField::Id => a.id.cmp(&b.id),
+                SortField::Preview => a.content_preview.to_lowercase().cmp(&b.content_preview.to_lowercase()),
+            }
+            .then(a.id.cmp(&b.id));
+            if self.descending {
+                order.reverse()
+            } else {
+                order
+            }
+        });
+    }
+
+    pub fn describe(&self) -> String {
+        format!("Ordem: {} {}", self.field.label(), if self.descending { "↓" } else { "↑" })
+    }
+
+    fn to_preference(self) -> String {
+        if self.descending {
+            format!("{} desc", self.field.key())
+        } else {
+            self.field.key().to_owned()
+        }
+    }
+
+    fn from_preference(value: &str) -> TaskSort {
+        let mut parts = value.split_whitespace();
+        let field = parts.next().and_then(|key| SortField::ALL.iter().find(|field| field.key() == key));
+        TaskSort {
+            field: field.copied().unwrap_or(SortField::EndDate),
+            descending: parts.next() == Some("desc"),
+        }
+    }
+}
+
+/// Which tasks the Monitor list shows.
+#[derive(Clone, Debug, Default)]
+pub struct TaskFilter {
+    pub project: Option<String>,
+    /// The "Concluídas" view: done and cancelled tasks instead of open ones.
+    pub closed: bool,
+}
+
+impl TaskFilter {
+    pub fn matches(&self, task: &Task) -> bool {
+        task.status().is_closed() == self.closed
+            && self.project.as_ref().map_or(true, |project| task.project == *project)
+    }
+
+    /// Active filters for the list title, e.g. "Projeto: Site".
+    pub fn describe(&self) -> Vec<String> {
+        let mut parts = Vec::new();
+        if self.closed {
+            parts.push(String::from("Concluídas"));
+        }
+        if let Some(project) = &self.project {
+            parts.push(format!("Projeto: {}", project));
+        }
+        parts
+    }
+}
+
+/// UI state, with the stored records kept in memory between frames.
+///
+/// `all_tasks`, `comments` and `projects` are what the server last sent with the
+/// pending outbox entries applied on top, see `refresh_view`; `tasks` is the part
+/// of `all_tasks` that passes `task_filter`, ordered by `task_sort`.
+pub struct App {
+    pub active_menu_item: MenuItem,
+    pub tasks: Vec<Task>,
+    pub all_tasks: Vec<Task>,
+    pub task_filter: TaskFilter,
+    pub task_sort: TaskSort,
+    pub comments: Vec<Comment>,
+    pub projects: Vec<Project>,
+    pub outbox: Outbox,
+    pub tasks_list_state: ListState,
+    pub comments_list_state: ListState,
+    pub projects_list_state: ListState,
+    pub outbox_list_state: ListState,
+    pub syncing: bool,
+    pub status: Option<Status>,
+    pub failure: Option<Failure>,
+    pub modal: Option<Modal>,
+    /// First visible line of the selected task's comment thread.
+    pub thread_scroll: u16,
+    pub calendar: Calendar,
+    /// Customer documents and contacts are masked unless revealed for this session.
+    pub reveal_customers: bool,
+    /// Set by `always_mask` in the config file, revealing is then disabled.
+    pub always_mask: bool,
+    /// How close a deadline must be to flag the task as due soon.
+    pub due_soon: chrono::Duration,
+    /// Directory of the store and its lock, see `Settings::data_dir`.
+    pub data_dir: Option<PathBuf>,
+    store: Box<dyn Store>,
+    revision: Option<i64>,
+    cached_tasks: Vec<Task>,
+    cached_comments: Vec<Comment>,
+    cached_projects: Vec<Project>,
+}
+
+impl App {
+    pub fn new(store: Box<dyn Store>) -> App {
+        let mut tasks_list_state = ListState::default();
+        tasks_list_state.select(Some(0));
+        let mut comments_list_state = ListState::default();
+        comments_list_state.select(Some(0));
+        let mut projects_list_state = ListState::default();
+        projects_list_state.select(Some(0));
+        let mut outbox_list_state = ListState::default();
+        outbox_list_state.select(Some(0));
+
+        let task_filter = TaskFilter {
+            project: store.preference(PROJECT_FILTER_KEY).ok().flatten(),
+            closed: false,
+        };
+        let task_sort = match store.preference(SORT_KEY) {
+            Ok(Some(value)) => TaskSort::from_preference(&value),
+            _ => TaskSort::default(),
+        };
+
+        App {
+            active_menu_item: MenuItem::Home,
+            tasks: Vec::new(),
+            all_tasks: Vec::new(),
+            task_filter,
+            task_sort,
+            comments: Vec::new(),
+            projects: Vec::new(),
+            outbox: Outbox::default(),
+            tasks_list_state,
+            comments_list_state,
+            projects_list_state,
+            outbox_list_state,
+            syncing: false,
+            status: None,
+            failure: None,
+            modal: None,
+            thread_scroll: 0,
+            calendar: Calendar::new(chrono::Utc::now().naive_utc().date()),
+            reveal_customers: false,
+            always_mask: false,
+            due_soon: chrono::Duration::days(DEFAULT_DUE_SOON_DAYS),
+            data_dir: None,
+            store,
+            revision: None,
+            cached_tasks: Vec::new(),
+            cached_comments: Vec::new(),
+            cached_projects: Vec::new(),
+        }
+    }
+
+    /// Reloads everything from the store if another connection (a sync, or
+    /// `codeplan-updater`) committed since the last load.
+    ///
+    /// On failure the data already in memory is kept and the load is retried
+    /// on the next call.
+    pub fn reload(&mut self) -> Result<(), Error> {
+        // Skipped while a sync holds the store, the next tick picks the changes up.
+        let _lock = match &self.data_dir {
+            Some(dir) => match StoreLock::try_shared(&dir.join(LOCK_FILE))? {
+                Some(lock) => Some(lock),
+                None => return Ok(()),
+            },
+            None => None,
+        };
+        let revision = self.store.revision()?;
+        if self.revision == Some(revision) {
+            return Ok(());
+        }
+        let tasks = self.store.tasks()?;
+        let comments = self.store.comments()?;
+        let projects = self.store.projects()?;
+        let outbox = Outbox::load(self.store.as_ref())?;
+
+        self.cached_tasks = tasks;
+        self.cached_comments = comments;
+        self.cached_projects = projects;
+        self.outbox = outbox;
+        self.revision = Some(revision);
+        self.refresh_view();
+        Ok(())
+    }
+
+    /// Rebuilds `tasks`, `comments` and `projects` from the cache plus the pending outbox entries.
+    ///
+    /// The selected task stays selected as long as it is still listed.
+    pub fn refresh_view(&mut self) {
+        let selected = self.selected_task().map(|task| task.id);
+        let mut tasks = self.cached_tasks.clone();
+        let mut comments = self.cached_comments.clone();
+        let mut projects = self.cached_projects.clone();
+        for entry in self.outbox.entries.iter().filter(|entry| entry.is_pending()) {
+            entry.apply(&mut tasks, &mut comments, &mut projects);
+        }
+        self.tasks = tasks.iter().filter(|task| self.task_filter.matches(task)).cloned().collect();
+        self.task_sort.sort(&mut self.tasks);
+        self.all_tasks = tasks;
+        self.comments = comments;
+        self.projects = projects;
+
+        let index = selected.and_then(|id| self.tasks.iter().position(|task| task.id == id));
+        if let Some(index) = index {
+            self.tasks_list_state.select(Some(index));
+        }
+        clamp_selection(&mut self.tasks_list_state, self.tasks.len());
+        clamp_selection(&mut self.comments_list_state, self.comments.len());
+        clamp_selection(&mut self.projects_list_state, self.projects.len());
+        clamp_selection(&mut self.outbox_list_state, self.outbox.entries.len());
+    }
+
+    /// Records a local change, shows it right away and persists it until it reaches the server.
+    ///
+    /// Returns the id of the outbox entry, which `models::unsynced_id` turns into the
+    /// placeholder id of a record the change creates.
+    pub fn queue(&mut self, mutation: Mutation) -> Result<u64, Error> {
+        let id = self.outbox.push(self.store.as_mut(), mutation)?;
+        self.refresh_view();
+        Ok(id)
+    }
+
+    pub fn retry_outbox_entry(&mut self) -> Result<(), Error> {
+        if let Some(id) = self.selected_outbox_entry_id() {
+            self.outbox.retry(self.store.as_mut(), id)?;
+            self.refresh_view();
+        }
+        Ok(())
+    }
+
+    pub fn discard_outbox_entry(&mut self, id: u64) -> Result<(), Error> {
+        self.outbox.remove(self.store.as_mut(), id)?;
+        self.refresh_view();
+        Ok(())
+    }
+
+    pub fn selected_outbox_entry_id(&self) -> Option<u64> {
+        self.outbox_list_state
+            .selected()
+            .and_then(|selected| self.outbox.entries.get(selected))
+            .map(|entry| entry.id)
+    }
+
+    pub fn set_status(&mut self, kind: StatusKind, message: String) {
+        self.status = Some(Status { kind, message });
+    }
+
+    /// Records `error` for the Error screen and mentions it in the status bar.
+    ///
+    /// Reporting the same failure again leaves the status bar alone, so retries
+    /// on every tick don't hide other messages.
+    pub fn report(&mut self, action: &str, error: &Error) {
+        let failure = self.failure_for(action, error);
+        if self.failure.as_ref() == Some(&failure) {
+            return;
+        }
+        self.set_status(
+            StatusKind::Failure,
+            format!("{}: {} (x para detalhes)", failure.action, failure.cause),
+        );
+        self.failure = Some(failure);
+    }
+
+    /// `Failure::new`, pointing store errors at this server's database.
+    fn failure_for(&self, action: &str, error: &Error) -> Failure {
+        let mut failure = Failure::new(action, error);
+        if let (Error::StoreError(_), Some(dir)) = (error, &self.data_dir) {
+            failure.location = Some(dir.join(DB_FILE).display().to_string());
+        }
+        failure
+    }
+
+    pub fn on_sync_event(&mut self, event: SyncEvent) {
+        match event {
+            SyncEvent::Replaying(count) => self.set_status(
+                StatusKind::Info,
+                format!("Enviando {} operações pendentes...", count),
+            ),
+            SyncEvent::Started(resource) => self.set_status(
+                StatusKind::Info,
+                format!("Sincronizando {}...", resource_label(resource)),
+            ),
+            SyncEvent::Finished(resource, count) => self.set_status(
+                StatusKind::Info,
+                format!("{} {} recebidos", count, resource_label(resource)),
+            ),
+            SyncEvent::Failed(resource, e) => self.set_status(
+                StatusKind::Failure,
+                format!("Falha ao sincronizar {}: {}", resource_label(resource), e),
+            ),
+        }
+    }
+
+    /// Takes the freshly synced records, the store already holds the same data.
+    pub fn apply_sync(&mut self, report: SyncReport) -> Result<(), Error> {
+        self.syncing = false;
+        // Another process may have queued entries meanwhile, so keep the store's copy.
+        let saved = match self.outbox.apply_replay(self.store.as_mut(), &report.replay) {
+            Ok(()) => Outbox::load(self.store.as_ref()).map(|outbox| self.outbox = outbox),
+            Err(e) => Err(e),
+        };
+        if let Some(tasks) = report.tasks {
+            self.cached_tasks = tasks;
+        }
+        if let Some(comments) = report.comments {
+            self.cached_comments = comments;
+        }
+        if let Some(projects) = report.projects {
+            self.cached_projects = projects;
+        }
+        self.revision = self.store.revision().ok();
+        self.refresh_view();
+
+        if let Some(e) = &report.replay.interrupted {
+            self.failure = Some(self.failure_for("Enviar operações pendentes", e));
+            self.set_status(
+                StatusKind::Failure,
+                format!("Servidor indisponível, operações mantidas na fila: {} (x para detalhes)", e),
+            );
+        } else if !report.replay.rejected.is_empty() {
+            self.set_status(
+                StatusKind::Failure,
+                format!(
+                    "{} operações recusadas pelo servidor, veja a aba Operações",
+                    report.replay.rejected.len()
+                ),
+            );
+        } else if report.failures.is_empty() {
+            self.set_status(
+                StatusKind::Success,
+                format!(
+                    "Sincronizado: {} tasks, {} comentários, {} projetos",
+                    self.all_tasks.len(),
+                    self.comments.len(),
+                    self.projects.len()
+                ),
+            );
+        } else {
+            if let Some((resource, e)) = report.failures.first() {
+                let action = format!("Sincronizar {}", resource_label(*resource));
+                self.failure = Some(self.failure_for(&action, e));
+            }
+            let failures: Vec<String> = report
+                .failures
+                .iter()
+                .map(|(resource, e)| format!("{} ({})", resource_label(*resource), e))
+                .collect();
+            self.set_status(
+                StatusKind::Failure,
+                format!("Falha ao sincronizar {} (x para detalhes)", failures.join(", ")),
+            );
+        }
+        saved
+    }
+
+    pub fn selected_task(&self) -> Option<&Task> {
+        self.tasks_list_state
+            .selected()
+            .and_then(|selected| self.tasks.get(selected))
+    }
+
+    /// Comments on `task`, oldest first.
+    pub fn thread(&self, task: &Task) -> Vec<&Comment> {
+        let mut thread: Vec<&Comment> = self.comments.iter().filter(|comment| comment.is_on(task)).collect();
+        thread.sort_by_key(|comment| comment.created_at);
+        thread
+    }
+
+    pub fn selected_project(&self) -> Option<&Project> {
+        self.projects_list_state
+            .selected()
+            .and_then(|selected| self.projects.get(selected))
+    }
+
+    /// The selected task, if it can be changed: see `is_synced`.
+    pub fn selected_synced_task(&mut self) -> Option<Task> {
+        let task = self.selected_task()?.clone();
+        if self.is_synced(task.id) {
+            Some(task)
+        } else {
+            None
+        }
+    }
+
+    /// The selected project, if it can be changed: see `is_synced`.
+    pub fn selected_synced_project(&mut self) -> Option<Project> {
+        let project = self.selected_project()?.clone();
+        if self.is_synced(project.id) {
+            Some(project)
+        } else {
+            None
+        }
+    }
+
+    /// The task of the selected comment, if it can be changed: see `is_synced`.
+    pub fn selected_synced_comment_task(&mut self) -> Option<Task> {
+        let task = self.selected_comment_task()?.clone();
+        if self.is_synced(task.id) {
+            Some(task)
+        } else {
+            None
+        }
+    }
+
+    /// Records created offline have no server id until the next sync, so changes
+    /// to them would go nowhere; the status bar says so when `id` is one of those.
+    fn is_synced(&mut self, id: usize) -> bool {
+        if is_unsynced(id) {
+            self.set_status(StatusKind::Info, String::from("Ainda não sincronizado: use (u) antes de alterar"));
+        }
+        !is_unsynced(id)
+    }
+
+    /// Shows only the tasks of `project`, or every task with `None`, and remembers it.
+    pub fn set_project_filter(&mut self, project: Option<String>) -> Result<(), Error> {
+        self.task_filter.project = project;
+        self.refresh_view();
+        self.store.set_preference(PROJECT_FILTER_KEY, self.task_filter.project.as_deref())
+    }
+
+    /// Switches between open tasks and the "Concluídas" view.
+    pub fn toggle_closed_view(&mut self) {
+        self.task_filter.closed = !self.task_filter.closed;
+        self.thread_scroll = 0;
+        self.refresh_view();
+    }
+
+    /// Changes the Monitor order, keeping the selected task selected, and remembers it.
+    pub fn set_task_sort(&mut self, sort: TaskSort) -> Result<(), Error> {
+        self.task_sort = sort;
+        self.refresh_view();
+        self.store.set_preference(SORT_KEY, Some(&sort.to_preference()))
+    }
+
+    /// Project names to filter by: the synced projects plus any other name tasks use.
+    pub fn project_names(&self) -> Vec<String> {
+        let mut names: Vec<String> = self
+            .projects
+            .iter()
+            .map(|project| project.name.clone())
+            .chain(self.all_tasks.iter().map(|task| task.project.clone()))
+            .collect();
+        names.sort();
+        names.dedup();
+        names
+    }
+
+    pub fn toggle_reveal_customers(&mut self) {
+        if self.always_mask {
+            self.set_status(
+                StatusKind::Info,
+                String::from("Os documentos ficam sempre mascarados (always_mask na configuração)"),
+            );
+            return;
+        }
+        self.reveal_customers = !self.reveal_customers;
+    }
+
+    /// The `/` prompt, while it filters the list of `scope`.
+    pub fn list_search(&self, scope: SearchScope) -> Option<&Search> {
+        match &self.modal {
+            Some(Modal::Search(search)) if search.scope == scope => Some(search),
+            _ => None,
+        }
+    }
+
+    pub fn masks_customers(&self) -> bool {
+        self.always_mask || !self.reveal_customers
+    }
+
+    pub fn selected_comment(&self) -> Option<&Comment> {
+        self.comments_list_state
+            .selected()
+            .and_then(|selected| self.comments.get(selected))
+    }
+
+    /// Task the selected comment was made on, to reply in the same thread.
+    pub fn selected_comment_task(&self) -> Option<&Task> {
+        let comment = self.selected_comment()?;
+        self.all_tasks.iter().find(|task| comment.is_on(task))
+    }
+}
+
+/// Keeps `state` on an existing item: nothing is selected while the list is
+/// empty, and the first item gets selected once something shows up.
+pub fn clamp_selection(state: &mut ListState, len: usize) {
+    if len == 0 {
+        state.select(None);
+    } else {
+        state.select(Some(state.selected().unwrap_or(0).min(len - 1)));
+    }
+}
+
+/// Moves the selection down, wrapping around at the end of the list.
+pub fn select_next(state: &mut ListState, len: usize) {
+    if len == 0 {
+        return;
+    }
+    match state.selected() {
+        Some(selected) if selected + 1 < len => state.select(Some(selected + 1)),
+        _ => state.select(Some(0)),
+    }
+}
+
+/// Moves the selection up, wrapping around at the start of the list.
+pub fn select_previous(state: &mut ListState, len: usize) {
+    if len == 0 {
+        return;
+    }
+    match state.selected() {
+        Some(selected) if selected > 0 => state.select(Some(selected - 1)),
+        _ => state.select(Some(len - 1)),
+    }
+}
+
+pub fn status_label(status: TaskStatus) -> &'static str {
+    match status {
+        TaskStatus::Pending => "Pendente",
+        TaskStatus::InProgress => "Em andamento",
+        TaskStatus::Done => "Concluída",
+        TaskStatus::Cancelled => "Cancelada",
+    }
+}
+
+pub fn resource_label(resource: Resource) -> &'static str {
+    match resource {
+        Resource::Tasks => "tasks",
+        Resource::Comments => "comentários",
+        Resource::Projects => "projetos",
+    }
+}
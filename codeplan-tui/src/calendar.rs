@@ -0,0 +1,110 @@
+use chrono::prelude::*;
+use codeplan_core::Task;
+
+pub const MONTHS: [&str; 12] = [
+    "Janeiro", "Fevereiro", "Março", "Abril", "Maio", "Junho", "Julho", "Agosto", "Setembro", "Outubro", "Novembro", "Dezembro",
+];
+
+pub const WEEKDAYS: [&str; 7] = ["Dom", "Seg", "Ter", "Qua", "Qui", "Sex", "Sáb"];
+
+/// The Agenda tab: a month of task deadlines with one day selected.
+///
+/// Days are UTC dates, as the task forms read and write them.
+pub struct Calendar {
+    pub selected: NaiveDate,
+}
+
+impl Calendar {
+    pub fn new(today: NaiveDate) -> Calendar {
+        Calendar { selected: today }
+    }
+
+    pub fn move_days(&mut self, days: i64) {
+        self.selected += chrono::Duration::days(days);
+    }
+
+    /// Keeps the day of the month, or the last one when the new month is shorter.
+    pub fn move_months(&mut self, months: i32) {
+        let index = self.selected.year() * 12 + self.selected.month0() as i32 + months;
+        let (year, month) = (index.div_euclid(12), index.rem_euclid(12) as u32 + 1);
+        let day = self.selected.day().min(days_in_month(year, month));
+        self.selected = NaiveDate::from_ymd(year, month, day);
+    }
+
+    /// E.g. "Outubro de 2026".
+    pub fn month_label(&self) -> String {
+        format!("{} de {}", MONTHS[self.selected.month0() as usize], self.selected.year())
+    }
+
+    /// Every day shown in the grid, a week per row from Sunday to Saturday,
+    /// including the days of the neighbouring months that fill the first and last weeks.
+    pub fn weeks(&self) -> Vec<[NaiveDate; 7]> {
+        let first = NaiveDate::from_ymd(self.selected.year(), self.selected.month(), 1);
+        let offset = first.weekday().num_days_from_sunday() as i64;
+        let count = (offset + days_in_month(first.year(), first.month()) as i64 + 6) / 7;
+        let start = first - chrono::Duration::days(offset);
+        (0..count)
+            .map(|week| {
+                let mut days = [start; 7];
+                for (weekday, day) in days.iter_mut().enumerate() {
+                    *day = start + chrono::Duration::days(week * 7 + weekday as i64);
+                }
+                days
+            })
+            .collect()
+    }
+}
+
+fn days_in_month(year: i32, month: u32) -> u32 {
+    let (next_year, next_month) = if month == 12 { (year + 1, 1) } else { (year, month + 1) };
+    NaiveDate::from_ymd(next_year, next_month, 1).pred().day()
+}
+
+/// Tasks whose `end_date` falls on `day`, in the order the day's list shows them.
+pub fn tasks_due_on(tasks: &[Task], day: NaiveDate) -> Vec<&Task> {
+    let mut due: Vec<&Task> = tasks.iter().filter(|task| task.end_date.naive_utc().date() == day).collect();
+    due.sort_by(|a, b| a.end_date.cmp(&b.end_date).then(a.id.cmp(&b.id)));
+    due
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn moved(year: i32, month: u32, day: u32, months: i32) -> NaiveDate {
+        let mut calendar = Calendar::new(NaiveDate::from_ymd(year, month, day));
+        calendar.move_months(months);
+        calendar.selected
+    }
+
+    #[test]
+    fn days_in_month_counts_leap_years_and_december() {
+        assert_eq!(days_in_month(2023, 2), 28);
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2100, 2), 28);
+        assert_eq!(days_in_month(2024, 4), 30);
+        assert_eq!(days_in_month(2024, 12), 31);
+    }
+
+    #[test]
+    fn move_months_clamps_to_the_last_day() {
+        assert_eq!(moved(2023, 1, 31, 1), NaiveDate::from_ymd(2023, 2, 28));
+        assert_eq!(moved(2024, 1, 31, 1), NaiveDate::from_ymd(2024, 2, 29));
+        assert_eq!(moved(2024, 3, 31, 1), NaiveDate::from_ymd(2024, 4, 30));
+        assert_eq!(moved(2024, 1, 15, 1), NaiveDate::from_ymd(2024, 2, 15));
+    }
+
+    #[test]
+    fn move_months_crosses_the_year() {
+        assert_eq!(moved(2024, 12, 31, 1), NaiveDate::from_ymd(2025, 1, 31));
+        assert_eq!(moved(2024, 11, 30, 14), NaiveDate::from_ymd(2026, 1, 30));
+    }
+
+    #[test]
+    fn move_months_goes_back() {
+        assert_eq!(moved(2025, 1, 31, -1), NaiveDate::from_ymd(2024, 12, 31));
+        assert_eq!(moved(2024, 3, 31, -1), NaiveDate::from_ymd(2024, 2, 29));
+        assert_eq!(moved(2025, 1, 10, -13), NaiveDate::from_ymd(2023, 12, 10));
+        assert_eq!(moved(2025, 5, 10, -12), NaiveDate::from_ymd(2024, 5, 10));
+    }
+}
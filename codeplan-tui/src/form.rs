@@ -0,0 +1,491 @@
+use std::collections::BTreeMap;
+
+use chrono::prelude::*;
+use codeplan_core::customer::{Contact, Document};
+use codeplan_core::models::{NewComment, NewProject, NewTask, ProjectPatch, TaskPatch};
+use codeplan_core::{Project, Task};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tui::{
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, Paragraph, Wrap},
+};
+
+pub const DATE_FORMAT: &str = "%d/%m/%Y";
+
+pub enum FieldKind {
+    Text,
+    MultiLine,
+    /// `dd/mm/aaaa`, see `parse_date`.
+    Date,
+    /// Cycled with Left/Right.
+    Choice(Vec<String>),
+}
+
+pub struct Field {
+    /// Name of the field in the API, used to match server errors.
+    pub name: &'static str,
+    pub label: &'static str,
+    pub kind: FieldKind,
+    pub value: String,
+    /// Value the form opened with, to send only what changed.
+    pub initial: String,
+    pub error: Option<String>,
+}
+
+impl Field {
+    pub fn text(name: &'static str, label: &'static str, value: &str) -> Field {
+        Field::new(name, label, FieldKind::Text, value.to_owned())
+    }
+
+    pub fn multi_line(name: &'static str, label: &'static str, value: &str) -> Field {
+        Field::new(name, label, FieldKind::MultiLine, value.to_owned())
+    }
+
+    pub fn date(name: &'static str, label: &'static str, value: Option<DateTime<Utc>>) -> Field {
+        let value = value.map(|date| date.format(DATE_FORMAT).to_string()).unwrap_or_default();
+        Field::new(name, label, FieldKind::Date, value)
+    }
+
+    /// Starts on `selected` if it is one of the options, on the first one otherwise.
+    pub fn choice(name: &'static str, label: &'static str, options: Vec<String>, selected: &str) -> Field {
+        let value = if options.iter().any(|option| option == selected) {
+            selected.to_owned()
+        } else {
+            options.first().cloned().unwrap_or_default()
+        };
+        Field::new(name, label, FieldKind::Choice(options), value)
+    }
+
+    fn new(name: &'static str, label: &'static str, kind: FieldKind, value: String) -> Field {
+        Field {
+            name,
+            label,
+            kind,
+            initial: value.clone(),
+            value,
+            error: None,
+        }
+    }
+
+    fn cycle(&mut self, step: isize) {
+        if let FieldKind::Choice(options) = &self.kind {
+            if options.is_empty() {
+                return;
+            }
+            let current = options.iter().position(|option| *option == self.value).unwrap_or(0);
+            let next = (current as isize + step).rem_euclid(options.len() as isize) as usize;
+            self.value = options[next].clone();
+        }
+    }
+}
+
+/// What a submitted form turns into.
+#[derive(Clone, Debug)]
+pub enum FormAction {
+    CreateTask,
+    EditTask(usize),
+    CreateComment { task_id: usize, task_preview: String },
+    CreateProject,
+    EditProject(usize),
+}
+
+/// Outcome of a key press inside a form.
+pub enum FormKey {
+    Continue,
+    Submit,
+    Cancel,
+}
+
+pub struct Form {
+    pub title: String,
+    pub fields: Vec<Field>,
+    pub focus: usize,
+    pub action: FormAction,
+    /// Error that doesn't belong to a single field.
+    pub error: Option<String>,
+    /// Waiting for the server; keys other than Esc are ignored meanwhile.
+    pub submitting: bool,
+}
+
+impl Form {
+    pub fn new(title: &str, fields: Vec<Field>, action: FormAction) -> Form {
+        Form {
+            title: title.to_owned(),
+            fields,
+            focus: 0,
+            action,
+            error: None,
+            submitting: false,
+        }
+    }
+
+    pub fn on_key(&mut self, event: KeyEvent) -> FormKey {
+        if self.submitting {
+            return match event.code {
+                KeyCode::Esc => FormKey::Cancel,
+                _ => FormKey::Continue,
+            };
+        }
+        let last = self.fields.len().saturating_sub(1);
+        match event.code {
+            KeyCode::Esc => return FormKey::Cancel,
+            KeyCode::Char('s') if event.modifiers.contains(KeyModifiers::CONTROL) => {
+                return FormKey::Submit
+            }
+            KeyCode::Tab | KeyCode::Down => self.focus = if self.focus >= last { 0 } else { self.focus + 1 },
+            KeyCode::BackTab | KeyCode::Up => self.focus = if self.focus == 0 { last } else { self.focus - 1 },
+            KeyCode::Enter => match self.fields[self.focus].kind {
+                FieldKind::MultiLine => self.edit(|value| value.push('\n')),
+                _ if self.focus >= last => return FormKey::Submit,
+                _ => self.focus += 1,
+            },
+            KeyCode::Left => self.fields[self.focus].cycle(-1),
+            KeyCode::Right => self.fields[self.focus].cycle(1),
+            KeyCode::Backspace => self.edit(|value| {
+                value.pop();
+            }),
+            KeyCode::Char(c) if !event.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.edit(|value| value.push(c))
+            }
+            _ => {}
+        }
+        FormKey::Continue
+    }
+
+    /// Applies `change` to the focused field, unless it is a choice.
+    fn edit<F: FnOnce(&mut String)>(&mut self, change: F) {
+        let field = &mut self.fields[self.focus];
+        if let FieldKind::Choice(_) = field.kind {
+            return;
+        }
+        change(&mut field.value);
+        field.error = None;
+    }
+
+    pub fn field(&self, name: &str) -> Option<&Field> {
+        self.fields.iter().find(|field| field.name == name)
+    }
+
+    pub fn value(&self, name: &str) -> &str {
+        self.field(name).map(|field| field.value.as_str()).unwrap_or("")
+    }
+
+    pub fn changed(&self, name: &str) -> bool {
+        self.field(name).map(|field| field.value != field.initial).unwrap_or(false)
+    }
+
+    /// Puts `error` next to the field called `name`, returning false if there is none.
+    pub fn set_error(&mut self, name: &str, error: &str) -> bool {
+        match self.fields.iter_mut().find(|field| field.name == name) {
+            Some(field) => {
+                field.error = Some(error.to_owned());
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn clear_errors(&mut self) {
+        self.error = None;
+        for field in &mut self.fields {
+            field.error = None;
+        }
+    }
+
+    pub fn has_errors(&self) -> bool {
+        self.error.is_some() || self.fields.iter().any(|field| field.error.is_some())
+    }
+
+    /// Shows the server's validation errors next to their fields.
+    pub fn set_server_errors(&mut self, fields: &BTreeMap<String, Vec<String>>) {
+        self.submitting = false;
+        let mut others = Vec::new();
+        for (name, errors) in fields {
+            if !self.set_error(name, &errors.join(", ")) {
+                others.push(format!("{}: {}", name, errors.join(", ")));
+            }
+        }
+        if !others.is_empty() || fields.is_empty() {
+            self.error = Some(if others.is_empty() {
+                String::from("O servidor recusou os dados")
+            } else {
+                others.join("; ")
+            });
+        }
+    }
+
+    /// Marks `name` as required, returning its trimmed value when filled in.
+    fn required(&mut self, name: &str) -> Option<String> {
+        let value = self.value(name).trim().to_owned();
+        if value.is_empty() {
+            self.set_error(name, "Campo obrigatório");
+            return None;
+        }
+        Some(value)
+    }
+
+    fn required_date(&mut self, name: &str) -> Option<DateTime<Utc>> {
+        let value = self.required(name)?;
+        let date = parse_date(&value);
+        if date.is_none() {
+            self.set_error(name, "Data inválida, use dd/mm/aaaa");
+        }
+        date
+    }
+}
+
+pub fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let date = NaiveDate::parse_from_str(value.trim(), DATE_FORMAT).ok()?;
+    Some(Utc.from_utc_datetime(&date.and_hms(0, 0, 0)))
+}
+
+pub fn new_task_form(projects: &[Project]) -> Form {
+    let names = projects.iter().map(|project| project.name.clone()).collect();
+    Form::new(
+        "Nova task",
+        vec![
+            Field::choice("project", "Projeto", names, ""),
+            Field::text("content_preview", "Título", ""),
+            Field::multi_line("content", "Descrição", ""),
+            Field::date("begin_date", "Início", Some(Utc::now())),
+            Field::date("end_date", "Entrega", None),
+        ],
+        FormAction::CreateTask,
+    )
+}
+
+pub fn edit_task_form(task: &Task, projects: &[Project]) -> Form {
+    let mut names: Vec<String> = projects.iter().map(|project| project.name.clone()).collect();
+    // Keep the current project even if it isn't synced, so saving doesn't move the task.
+    if !names.contains(&task.project) {
+        names.insert(0, task.project.clone());
+    }
+    Form::new(
+        "Editar task",
+        vec![
+            Field::choice("project", "Projeto", names, &task.project),
+            Field::text("content_preview", "Título", &task.content_preview),
+            Field::multi_line("content", "Descrição", &task.content),
+            Field::date("begin_date", "Início", Some(task.begin_date)),
+            Field::date("end_date", "Entrega", Some(task.end_date)),
+        ],
+        FormAction::EditTask(task.id),
+    )
+}
+
+/// Validates the task form, leaving an error on each invalid field.
+pub fn read_new_task(form: &mut Form) -> Option<NewTask> {
+    form.clear_errors();
+    if form.value("project").is_empty() {
+        form.set_error("project", "Nenhum projeto disponível, sincronize primeiro (u)");
+    }
+    let content_preview = form.required("content_preview");
+    let begin_date = form.required_date("begin_date");
+    let end_date = form.required_date("end_date");
+    if let (Some(begin), Some(end)) = (begin_date, end_date) {
+        if end < begin {
+            form.set_error("end_date", "A entrega deve ser depois do início");
+        }
+    }
+    if form.has_errors() {
+        return None;
+    }
+    Some(NewTask {
+        project: form.value("project").to_owned(),
+        content_preview: content_preview?,
+        content: form.value("content").trim().to_owned(),
+        begin_date: begin_date?,
+        end_date: end_date?,
+    })
+}
+
+/// Validates the edit form and keeps only the fields that changed.
+pub fn read_task_patch(form: &mut Form) -> Option<TaskPatch> {
+    let task = read_new_task(form)?;
+    Some(TaskPatch {
+        project: Some(task.project).filter(|_| form.changed("project")),
+        content_preview: Some(task.content_preview).filter(|_| form.changed("content_preview")),
+        content: Some(task.content).filter(|_| form.changed("content")),
+        begin_date: Some(task.begin_date).filter(|_| form.changed("begin_date")),
+        end_date: Some(task.end_date).filter(|_| form.changed("end_date")),
+    })
+}
+
+pub fn new_comment_form(task_id: usize, task_preview: &str) -> Form {
+    Form::new(
+        &format!("Comentar em {}", task_preview),
+        vec![Field::multi_line("content", "Comentário", "")],
+        FormAction::CreateComment {
+            task_id,
+            task_preview: task_preview.to_owned(),
+        },
+    )
+}
+
+pub fn read_new_comment(form: &mut Form, task_id: usize, task_preview: &str) -> Option<NewComment> {
+    form.clear_errors();
+    let content = form.required("content")?;
+    Some(NewComment {
+        task_id,
+        task_preview: task_preview.to_owned(),
+        content,
+    })
+}
+
+pub fn new_project_form() -> Form {
+    Form::new(
+        "Novo projeto",
+        vec![
+            Field::text("name", "Nome", ""),
+            Field::text("customer_name", "Cliente", ""),
+            Field::text("customer_document", "CPF/CNPJ do cliente", ""),
+            Field::text("customer_contact", "Contato do cliente (e-mail ou telefone)", ""),
+        ],
+        FormAction::CreateProject,
+    )
+}
+
+/// With `masked`, the document and contact start out masked like on the Projects
+/// tab; they are only sent when retyped, see `read_project_patch`.
+pub fn edit_project_form(project: &Project, masked: bool) -> Form {
+    let (shown, document_label, contact_label) = if masked {
+        (
+            project.masked(),
+            "CPF/CNPJ do cliente (oculto, digite de novo para alterar)",
+            "Contato do cliente (oculto, digite de novo para alterar)",
+        )
+    } else {
+        (project.clone(), "CPF/CNPJ do cliente", "Contato do cliente (e-mail ou telefone)")
+    };
+    Form::new(
+        "Editar projeto",
+        vec![
+            Field::text("name", "Nome", &shown.name),
+            Field::text("customer_name", "Cliente", &shown.customer_name),
+            Field::text("customer_document", document_label, &shown.customer_document),
+            Field::text("customer_contact", contact_label, &shown.customer_contact),
+        ],
+        FormAction::EditProject(project.id),
+    )
+}
+
+/// Validates the project form, with the document and contact in their standard format.
+pub fn read_new_project(form: &mut Form) -> Option<NewProject> {
+    form.clear_errors();
+    let name = form.required("name");
+    let customer_name = form.required("customer_name");
+    let customer_document = read_document(form);
+    let customer_contact = read_contact(form);
+    if form.has_errors() {
+        return None;
+    }
+    Some(NewProject {
+        name: name?,
+        customer_name: customer_name?,
+        customer_document: customer_document?,
+        customer_contact: customer_contact?,
+    })
+}
+
+/// Validates only the fields that were changed, the others are left out of the patch.
+pub fn read_project_patch(form: &mut Form) -> Option<ProjectPatch> {
+    form.clear_errors();
+    let name = if form.changed("name") { form.required("name") } else { None };
+    let customer_name = if form.changed("customer_name") { form.required("customer_name") } else { None };
+    let customer_document = if form.changed("customer_document") { read_document(form) } else { None };
+    let customer_contact = if form.changed("customer_contact") { read_contact(form) } else { None };
+    if form.has_errors() {
+        return None;
+    }
+    Some(ProjectPatch {
+        name,
+        customer_name,
+        customer_document,
+        customer_contact,
+    })
+}
+
+fn read_document(form: &mut Form) -> Option<String> {
+    let value = form.required("customer_document")?;
+    match Document::parse(&value) {
+        Some(document) => Some(document.to_string()),
+        None => {
+            form.set_error("customer_document", "CPF ou CNPJ inválido, confira os dígitos");
+            None
+        }
+    }
+}
+
+fn read_contact(form: &mut Form) -> Option<String> {
+    let value = form.required("customer_contact")?;
+    match Contact::parse(&value) {
+        Some(contact) => Some(contact.to_string()),
+        None => {
+            form.set_error("customer_contact", "Informe um e-mail ou um telefone com DDD");
+            None
+        }
+    }
+}
+
+pub fn render_form<'a>(form: &'a Form) -> Paragraph<'a> {
+    let mut lines = Vec::new();
+    for (index, field) in form.fields.iter().enumerate() {
+        let focused = index == form.focus;
+        let label_style = if focused {
+            Style::default().fg(Color::LightCyan).add_modifier(Modifier::BOLD)
+        } else {
+            Style::default().fg(Color::Gray)
+        };
+        lines.push(Spans::from(vec![Span::styled(
+            format!("{} {}", if focused { ">" } else { " " }, field.label),
+            label_style,
+        )]));
+
+        let cursor = if focused { "_" } else { "" };
+        match &field.kind {
+            FieldKind::Choice(_) if field.value.is_empty() => {
+                lines.push(Spans::from(vec![Span::raw("    (nenhuma opção)")]));
+            }
+            FieldKind::Choice(_) => {
+                lines.push(Spans::from(vec![Span::raw(format!("    ◀ {} ▶", field.value))]));
+            }
+            FieldKind::MultiLine => {
+                let mut value_lines: Vec<&str> = field.value.split('\n').collect();
+                let last = value_lines.pop().unwrap_or("");
+                for line in value_lines {
+                    lines.push(Spans::from(vec![Span::raw(format!("    {}", line))]));
+                }
+                lines.push(Spans::from(vec![Span::raw(format!("    {}{}", last, cursor))]));
+            }
+            FieldKind::Text | FieldKind::Date => {
+                lines.push(Spans::from(vec![Span::raw(format!("    {}{}", field.value, cursor))]));
+            }
+        }
+        if let Some(error) = &field.error {
+            lines.push(Spans::from(vec![Span::styled(
+                format!("    {}", error),
+                Style::default().fg(Color::Red),
+            )]));
+        }
+    }
+    lines.push(Spans::from(vec![Span::raw("")]));
+    if let Some(error) = &form.error {
+        lines.push(Spans::from(vec![Span::styled(error.as_str(), Style::default().fg(Color::Red))]));
+    }
+    if form.submitting {
+        lines.push(Spans::from(vec![Span::styled("Enviando...", Style::default().fg(Color::Yellow))]));
+    }
+    lines.push(Spans::from(vec![Span::styled(
+        "(Tab) Próximo campo | (←/→) Alterar opção | (Ctrl+S) Salvar | (Esc) Cancelar",
+        Style::default().fg(Color::DarkGray),
+    )]));
+
+    Paragraph::new(lines).wrap(Wrap { trim: false }).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White))
+            .title(form.title.as_str())
+            .border_type(BorderType::Rounded),
+    )
+}
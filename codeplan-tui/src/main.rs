@@ -1,105 +1,98 @@
 use std::collections::hash_map;
-use std::fs;
 use std::future::Future;
 use std::io;
-use std::process::{Command, Stdio};
 use std::str::FromStr;
 use std::sync::mpsc;
 use std::thread;
 use std::time::{Duration, Instant};
 
 use chrono::prelude::*;
+use codeplan_core::client::Client;
+use codeplan_core::config::Settings;
+use codeplan_core::files::{StoreLock, LOCK_FILE};
+use codeplan_core::outbox::{Mutation, Outbox, OutboxEntry};
+use codeplan_core::store::{SqliteStore, DB_FILE};
+use codeplan_core::sync::{self, SyncEvent, SyncReport};
+use codeplan_core::models::{unsynced_id, Deadline};
+use codeplan_core::{Comment, Error, Project, Task};
 use crossterm::{
-    event::{self, Event as CEvent, KeyCode},
+    event::{self, Event as CEvent, KeyCode, KeyEvent, KeyModifiers},
     terminal::{disable_raw_mode, enable_raw_mode},
 };
 use rand::{distributions::Alphanumeric, prelude::*};
-use serde::{Deserialize, ser, Serialize};
-use thiserror::Error;
 use tui::{
     backend::CrosstermBackend,
     layout::{Alignment, Constraint, Direction, Layout},
     style::{Color, Modifier, Style},
     Terminal,
-    text::{Span, Spans},
+    text::{Span, Spans, Text},
     widgets::{
-        Block, Borders, BorderType, Cell, List, ListItem, ListState, Paragraph, Row, Table, Tabs,
+        Block, Borders, BorderType, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Table, Tabs, Wrap,
     },
 };
 
-const TASK_PATH: &str = "./cache/task.json";
-const COMMENT_PATH: &str = "./cache/comment.json";
-const PROJECT_PATH: &str = "./cache/project.json";
+use crate::app::{select_next, select_previous, status_label, App, Failure, MenuItem, Status, StatusKind, TaskFilter, TaskSort};
+use crate::calendar::{tasks_due_on, Calendar, WEEKDAYS};
+use crate::form::{
+    edit_project_form, edit_task_form, new_comment_form, new_project_form, new_task_form, read_new_comment,
+    read_new_project, read_new_task, read_project_patch, read_task_patch, render_form, Form, FormAction, FormKey,
+    DATE_FORMAT};
+use crate::modal::{centered_rect, render_confirm, render_picker, Confirm, ConfirmAction, Modal, Picker, PickerAction};
+use crate::search::{
+    render_search_list, render_search_prompt, render_search_results, search_layout, Search, SearchScope, SearchTarget,
+};
 
-#[derive(Error, Debug)]
-pub enum Error {
-    #[error("error reading the DB file: {0}")]
-    ReadDBError(#[from] io::Error),
-    #[error("error parsing the DB file: {0}")]
-    ParseDBError(#[from] serde_json::Error),
-}
+mod app;
+mod calendar;
+mod form;
+mod modal;
+mod search;
 
 enum Event<I> {
     Input(I),
     Tick,
+    Sync(SyncEvent),
+    SyncDone(SyncReport),
+    /// A form's change was sent straight to the server.
+    Submitted(Mutation, Result<(), Error>),
 }
 
-#[derive(Serialize, Deserialize, Clone)]
-struct Task {
-    id: usize,
-    project: String,
-    content_preview: String,
-    content: String,
-    begin_date: DateTime<Utc>,
-    end_date: DateTime<Utc>,
-    finish_date: DateTime<Utc>,
-}
-
-#[derive(Serialize, Deserialize, Clone)]
-struct Comment {
-    id: usize,
-    task_preview: String,
-    content: String,
-    created_at: DateTime<Utc>,
-}
-
-#[derive(Serialize, Deserialize, Clone)]
-struct Project {
-    id: usize,
-    name: String,
-    customer_name: String,
-    customer_document: String,
-    customer_contact: String,
-    created_at: DateTime<Utc>,
-}
-
-#[derive(Copy, Clone, Debug)]
-enum MenuItem {
-    Home,
-    Monitor,
-    Comments,
-    Projects,
-    License,
-    Error,
-}
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let args: Vec<String> = std::env::args().collect();
+    let started = Settings::from_args(&args).and_then(|settings| {
+        let client = Client::from_settings(&settings)?;
+        let store = SqliteStore::open(&settings.db_path())?;
+        Ok((settings, client, store))
+    });
+    let runtime = tokio::runtime::Runtime::new()?;
 
-impl<'a> From<MenuItem> for usize {
-    fn from(input: MenuItem) -> usize {
-        match input {
-            MenuItem::Home => 0,
-            MenuItem::Monitor => 1,
-            MenuItem::Comments => 2,
-            MenuItem::Projects => 3,
-            MenuItem::License => 4,
-            MenuItem::Error => 5,
+    // Without settings or a store there is nothing to show or sync, but the failure is
+    // still reported on the Error tab rather than after leaving the terminal. The
+    // app is built before raw mode so its own failures leave the terminal as it was.
+    let (mut app, client) = match started {
+        Ok((settings, client, store)) => {
+            let mut app = App::new(Box::new(store));
+            app.always_mask = settings.config.always_mask;
+            app.due_soon = chrono::Duration::days(settings.config.due_soon_days());
+            app.data_dir = Some(settings.data_dir.clone());
+            if let Err(e) = app.reload() {
+                app.report("Carregar dados locais", &e);
+                app.active_menu_item = MenuItem::Error;
+            }
+            (app, Some(client))
         }
-    }
-}
+        Err(e) => {
+            let mut app = App::new(Box::new(SqliteStore::open_in_memory()?));
+            app.report("Iniciar o codeplan", &e);
+            app.active_menu_item = MenuItem::Error;
+            (app, None)
+        }
+    };
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
-    enable_raw_mode().expect("can run in raw mode");
+    enable_raw_mode()?;
 
     let (tx, rx) = mpsc::channel();
+    let sync_tx = tx.clone();
     let tick_rate = Duration::from_millis(200);
     thread::spawn(move || {
         let mut last_tick = Instant::now();
@@ -108,9 +101,12 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .checked_sub(last_tick.elapsed())
                 .unwrap_or_else(|| Duration::from_secs(0));
 
-            if event::poll(timeout).expect("poll works") {
-                if let CEvent::Key(key) = event::read().expect("can read events") {
-                    tx.send(Event::Input(key)).expect("can send events");
+            // A failed read just drops that input; the UI only goes away when the receiver does.
+            if let Ok(true) = event::poll(timeout) {
+                if let Ok(CEvent::Key(key)) = event::read() {
+                    if tx.send(Event::Input(key)).is_err() {
+                        break;
+                    }
                 }
             }
 
@@ -127,21 +123,16 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut terminal = Terminal::new(backend)?;
     terminal.clear()?;
 
-    let menu_titles = vec!["Início", "Tasks", "Comentários (Tasks)", "Projetos", "Licença", "Sair"];
-    let mut active_menu_item = MenuItem::Home;
-    let mut tasks_list_state = ListState::default();
-    tasks_list_state.select(Some(0));
-    let mut comments_list_state = ListState::default();
-    comments_list_state.select(Some(0));
-    let mut projects_list_state = ListState::default();
-    projects_list_state.select(Some(0));
+    let menu_titles = vec!["Início", "Tasks", "Comentários (Tasks)", "Projetos", "Operações", "Agenda", "Licença", "Sair"];
 
 
     let home_position: usize = 0;
     let monitor_position: usize = 1;
     let comments_position: usize = 2;
     let projects_position: usize = 3;
-    let license_position: usize = 4;
+    let outbox_position: usize = 4;
+    let calendar_position: usize = 5;
+    let license_position: usize = 6;
 
     loop {
         terminal.draw(|rect| {
@@ -154,6 +145,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                         Constraint::Length(3),
                         Constraint::Min(2),
                         Constraint::Length(3),
+                        Constraint::Length(1),
                     ]
                         .as_ref(),
                 )
@@ -176,17 +168,18 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                 .collect();
 
             let tabs = Tabs::new(menu)
-                .select(active_menu_item.into())
+                .select(app.active_menu_item.into())
                 .block(Block::default().title("Menu").borders(Borders::ALL).border_type(BorderType::Rounded))
                 .style(Style::default().fg(Color::White))
                 .highlight_style(Style::default().fg(Color::White))
                 .divider(Span::raw("|"));
 
             rect.render_widget(tabs, chunks[0]);
-            match active_menu_item {
+            rect.render_widget(render_status(app.status.as_ref()), chunks[3]);
+            match app.active_menu_item {
                 MenuItem::Home => {
                     rect.render_widget(render_home(), chunks[1]);
-                    rect.render_widget(render_options("Nenhuma ação disponível"), chunks[2]);
+                    rect.render_widget(render_options("(/) Buscar em tasks, comentários e projetos"), chunks[2]);
                 }
                 MenuItem::Monitor => {
                     let tasks_chunks = Layout::default()
@@ -195,10 +188,35 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             [Constraint::Percentage(15), Constraint::Percentage(85)].as_ref(),
                         )
                         .split(chunks[1]);
-                    let (left, right) = render_monitor(&tasks_list_state);
-                    rect.render_stateful_widget(left, tasks_chunks[0], &mut tasks_list_state);
-                    rect.render_widget(right, tasks_chunks[1]);
-                    rect.render_widget(render_options("(f) Marcar como concluída | (d) Deletar"), chunks[2]);
+                    let detail_chunks = Layout::default()
+                        .direction(Direction::Vertical)
+                        .constraints([Constraint::Length(5), Constraint::Min(3)].as_ref())
+                        .split(tasks_chunks[1]);
+                    let title = monitor_title(&app.task_filter, &app.task_sort);
+                    if let Some(search) = app.list_search(SearchScope::Tasks) {
+                        let tasks = search.filtered(&app.tasks, |task| SearchTarget::Task(task.id));
+                        let mut state = search.state.clone();
+                        let (_, right) = render_monitor(&tasks, &state, String::new(), app.due_soon, NOTHING_FOUND);
+                        let thread = state.selected().and_then(|selected| tasks.get(selected)).map(|task| app.thread(task)).unwrap_or_default();
+                        rect.render_widget(render_thread(&thread, 0), detail_chunks[1]);
+                        rect.render_stateful_widget(render_search_list(&title, &search.results), tasks_chunks[0], &mut state);
+                        rect.render_widget(right, detail_chunks[0]);
+                        rect.render_widget(render_search_prompt(&search.query), chunks[2]);
+                    } else {
+                        let empty = monitor_empty_state(&app.task_filter, !app.all_tasks.is_empty());
+                        let (left, right) = render_monitor(&app.tasks, &app.tasks_list_state, title, app.due_soon, &empty);
+                        let thread = app.selected_task().map(|task| app.thread(task)).unwrap_or_default();
+                        rect.render_widget(render_thread(&thread, app.thread_scroll), detail_chunks[1]);
+                        rect.render_stateful_widget(left, tasks_chunks[0], &mut app.tasks_list_state);
+                        rect.render_widget(right, detail_chunks[0]);
+                        let options = match (app.selected_task(), app.task_filter.closed) {
+                            (Some(_), false) => "(n) Nova | (e) Editar | (m) Comentar | (f) Marcar como concluída | (d) Deletar | (v) Concluídas | (P) Projeto | (r/R) Ordenar | (/) Buscar | (PgUp/PgDn) Rolar comentários",
+                            (Some(_), true) => "(e) Editar | (m) Comentar | (f) Reabrir | (d) Deletar | (v) Abertas | (P) Projeto | (r/R) Ordenar | (/) Buscar | (PgUp/PgDn) Rolar comentários",
+                            (None, false) => "(n) Nova task | (v) Concluídas | (P) Projeto",
+                            (None, true) => "(v) Abertas | (P) Projeto",
+                        };
+                        rect.render_widget(render_options(options).block(options_block(deadline_legend())), chunks[2]);
+                    }
                 }
                 MenuItem::Comments => {
                     let tasks_chunks = Layout::default()
@@ -207,10 +225,23 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             [Constraint::Percentage(15), Constraint::Percentage(85)].as_ref(),
                         )
                         .split(chunks[1]);
-                    let (left, right) = render_comments(&comments_list_state);
-                    rect.render_stateful_widget(left, tasks_chunks[0], &mut comments_list_state);
-                    rect.render_widget(right, tasks_chunks[1]);
-                    rect.render_widget(render_options("Nenhuma ação disponível"), chunks[2]);
+                    if let Some(search) = app.list_search(SearchScope::Comments) {
+                        let comments = search.filtered(&app.comments, |comment| SearchTarget::Comment(comment.id));
+                        let mut state = search.state.clone();
+                        let (_, right) = render_comments(&comments, &state, NOTHING_FOUND);
+                        rect.render_stateful_widget(render_search_list("Comentários", &search.results), tasks_chunks[0], &mut state);
+                        rect.render_widget(right, tasks_chunks[1]);
+                        rect.render_widget(render_search_prompt(&search.query), chunks[2]);
+                    } else {
+                        let (left, right) = render_comments(&app.comments, &app.comments_list_state, "Nenhum comentário — pressione u para sincronizar");
+                        rect.render_stateful_widget(left, tasks_chunks[0], &mut app.comments_list_state);
+                        rect.render_widget(right, tasks_chunks[1]);
+                        let options = match app.selected_comment_task() {
+                            Some(_) => "(m) Comentar na mesma task | (/) Buscar",
+                            None => "(/) Buscar",
+                        };
+                        rect.render_widget(render_options(options), chunks[2]);
+                    }
                 }
                 MenuItem::Projects => {
                     let projects_chunks = Layout::default()
@@ -219,139 +250,700 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
                             [Constraint::Percentage(15), Constraint::Percentage(85)].as_ref(),
                         )
                         .split(chunks[1]);
-                    let (left, right) = render_projects(&projects_list_state);
-                    rect.render_stateful_widget(left, projects_chunks[0], &mut projects_list_state);
-                    rect.render_widget(right, projects_chunks[1]);
-                    rect.render_widget(render_options("Nenhuma ação disponível"), chunks[2]);
+                    if let Some(search) = app.list_search(SearchScope::Projects) {
+                        let projects = search.filtered(&app.projects, |project| SearchTarget::Project(project.id));
+                        let mut state = search.state.clone();
+                        let (_, right) = render_projects(&projects, &state, app.masks_customers(), NOTHING_FOUND);
+                        rect.render_stateful_widget(render_search_list("Projetos", &search.results), projects_chunks[0], &mut state);
+                        rect.render_widget(right, projects_chunks[1]);
+                        rect.render_widget(render_search_prompt(&search.query), chunks[2]);
+                    } else {
+                        let (left, right) = render_projects(&app.projects, &app.projects_list_state, app.masks_customers(), "Nenhum projeto — pressione u para sincronizar");
+                        rect.render_stateful_widget(left, projects_chunks[0], &mut app.projects_list_state);
+                        rect.render_widget(right, projects_chunks[1]);
+                        let options = match app.selected_project() {
+                            Some(_) if app.masks_customers() => "(n) Novo | (e) Editar | (v) Mostrar documentos | (/) Buscar",
+                            Some(_) => "(n) Novo | (e) Editar | (v) Ocultar documentos | (/) Buscar",
+                            None => "(n) Novo projeto",
+                        };
+                        rect.render_widget(render_options(options), chunks[2]);
+                    }
+                }
+                MenuItem::Outbox => {
+                    let outbox_chunks = Layout::default()
+                        .direction(Direction::Horizontal)
+                        .constraints(
+                            [Constraint::Percentage(30), Constraint::Percentage(70)].as_ref(),
+                        )
+                        .split(chunks[1]);
+                    let (left, right) = render_outbox(&app.outbox.entries, &app.outbox_list_state);
+                    rect.render_stateful_widget(left, outbox_chunks[0], &mut app.outbox_list_state);
+                    rect.render_widget(right, outbox_chunks[1]);
+                    let options = match app.outbox_list_state.selected() {
+                        Some(_) => "(r) Reenviar | (d) Descartar",
+                        None => "Nenhuma ação disponível",
+                    };
+                    rect.render_widget(render_options(options), chunks[2]);
+                }
+                MenuItem::Calendar => {
+                    rect.render_widget(render_calendar(&app.calendar, &app.all_tasks, app.due_soon), chunks[1]);
+                    rect.render_widget(
+                        render_options("(←/→/↑/↓) Dia | (PgUp/PgDn) Mês | (Enter) Tasks do dia").block(options_block(deadline_legend())),
+                        chunks[2],
+                    );
                 }
                 MenuItem::License => {
                     rect.render_widget(render_license(), chunks[1]);
                     rect.render_widget(render_options("Nenhuma ação disponível"), chunks[2]);
                 }
                 MenuItem::Error => {
-                    rect.render_widget(render_error("Ocorreu um erro :(", "Para mais informações, entre em contato", "com o desenvolvedor da aplicação."), chunks[1]);
-                    rect.render_widget(render_options("Nenhuma ação disponível"), chunks[2]);
+                    rect.render_widget(render_error(app.failure.as_ref()), chunks[1]);
+                    let options = match client {
+                        Some(_) => "(i) Voltar ao início",
+                        None => "(s) Sair",
+                    };
+                    rect.render_widget(render_options(options), chunks[2]);
                 }
             }
+            match app.modal.as_mut() {
+                // A scoped search is drawn in its tab, see `App::list_search`.
+                Some(Modal::Search(search)) if search.scope == SearchScope::All => {
+                    let area = centered_rect(70, 70, size);
+                    rect.render_widget(Clear, area);
+                    let (prompt_area, results_area) = search_layout(area);
+                    rect.render_widget(render_search_prompt(&search.query), prompt_area);
+                    let results = render_search_results(&search.query, &search.results);
+                    rect.render_stateful_widget(results, results_area, &mut search.state);
+                }
+                Some(Modal::Picker(picker)) => {
+                    let area = centered_rect(40, 60, size);
+                    rect.render_widget(Clear, area);
+                    let list = render_picker(&picker.title, &picker.options);
+                    rect.render_stateful_widget(list, area, &mut picker.state);
+                }
+                Some(Modal::Confirm(confirm)) => {
+                    let area = centered_rect(50, 30, size);
+                    rect.render_widget(Clear, area);
+                    rect.render_widget(render_confirm(confirm), area);
+                }
+                Some(Modal::Form(form)) => {
+                    let area = centered_rect(70, 80, size);
+                    rect.render_widget(Clear, area);
+                    rect.render_widget(render_form(form), area);
+                }
+                Some(Modal::Search(_)) | None => {}
+            }
         })?;
 
-        match rx.recv()? {
-            Event::Input(event) => match event.code {
+        match (rx.recv()?, client.as_ref()) {
+            (Event::Input(event), None) => {
+                if let KeyCode::Char('s') = event.code {
+                    disable_raw_mode()?;
+                    terminal.show_cursor()?;
+                    break;
+                }
+            }
+            (Event::Input(event), Some(client)) if app.modal.is_some() => on_modal_key(&mut app, event, &runtime, client, &sync_tx),
+            (Event::Input(event), Some(client)) => match event.code {
                 KeyCode::Char('s') => {
                     disable_raw_mode()?;
                     terminal.show_cursor()?;
                     break;
                 }
-                KeyCode::Char('i') => active_menu_item = MenuItem::Home,
-                KeyCode::Char('t') => active_menu_item = MenuItem::Monitor,
-                KeyCode::Char('c') => active_menu_item = MenuItem::Comments,
-                KeyCode::Char('p') => active_menu_item = MenuItem::Projects,
-                KeyCode::Char('l') => active_menu_item = MenuItem::License,
-                KeyCode::Char('u') => {
-                    Command::new("./codeplan-updater").stderr(Stdio::null()).spawn().expect("ls command failed to start");
+                KeyCode::Char('i') => app.active_menu_item = MenuItem::Home,
+                KeyCode::Char('t') => app.active_menu_item = MenuItem::Monitor,
+                KeyCode::Char('c') => app.active_menu_item = MenuItem::Comments,
+                KeyCode::Char('p') => app.active_menu_item = MenuItem::Projects,
+                KeyCode::Char('o') => app.active_menu_item = MenuItem::Outbox,
+                KeyCode::Char('a') => app.active_menu_item = MenuItem::Calendar,
+                KeyCode::Char('l') => app.active_menu_item = MenuItem::License,
+                KeyCode::Char('x') => {
+                    if app.failure.is_some() {
+                        app.active_menu_item = MenuItem::Error;
+                    }
+                }
+                KeyCode::Char('u') => request_sync(&mut app, &runtime, client, &sync_tx),
+                KeyCode::Char('n') => {
+                    let new_active_position: usize = From::<MenuItem>::from(app.active_menu_item);
+                    if new_active_position == monitor_position {
+                        app.modal = Some(Modal::Form(new_task_form(&app.projects)));
+                    } else if new_active_position == projects_position {
+                        app.modal = Some(Modal::Form(new_project_form()));
+                    }
+                }
+                KeyCode::Char('e') => {
+                    let edit_active_position: usize = From::<MenuItem>::from(app.active_menu_item);
+                    if edit_active_position == monitor_position {
+                        let form = app.selected_synced_task().map(|task| edit_task_form(&task, &app.projects));
+                        if let Some(form) = form {
+                            app.modal = Some(Modal::Form(form));
+                        }
+                    } else if edit_active_position == projects_position {
+                        let masked = app.masks_customers();
+                        if let Some(form) = app.selected_synced_project().map(|project| edit_project_form(&project, masked)) {
+                            app.modal = Some(Modal::Form(form));
+                        }
+                    }
+                }
+                KeyCode::Char('m') => {
+                    let comment_active_position: usize = From::<MenuItem>::from(app.active_menu_item);
+                    let task = if comment_active_position == monitor_position {
+                        app.selected_synced_task()
+                    } else if comment_active_position == comments_position {
+                        app.selected_synced_comment_task()
+                    } else {
+                        None
+                    };
+                    if let Some(form) = task.map(|task| new_comment_form(task.id, &task.content_preview)) {
+                        app.modal = Some(Modal::Form(form));
+                    }
+                }
+                KeyCode::Char('v') => {
+                    let reveal_active_position: usize = From::<MenuItem>::from(app.active_menu_item);
+                    if reveal_active_position == projects_position {
+                        app.toggle_reveal_customers();
+                    } else if reveal_active_position == monitor_position {
+                        app.toggle_closed_view();
+                    }
+                }
+                KeyCode::Char('/') => {
+                    let search = Search::new(SearchScope::of(app.active_menu_item), &app);
+                    app.modal = Some(Modal::Search(search));
+                }
+                KeyCode::Char('P') => {
+                    let filter_active_position: usize = From::<MenuItem>::from(app.active_menu_item);
+                    if filter_active_position == monitor_position {
+                        let names = app.project_names();
+                        let selected = app
+                            .task_filter
+                            .project
+                            .as_ref()
+                            .and_then(|project| names.iter().position(|name| name == project))
+                            .map_or(0, |index| index + 1);
+                        let mut options = vec![String::from("Todos os projetos")];
+                        options.extend(names);
+                        app.modal = Some(Modal::Picker(Picker::new("Filtrar por projeto", options, selected, PickerAction::ProjectFilter)));
+                    }
                 }
                 KeyCode::Char('f') => {
-                    let delete_active_position: usize = From::<MenuItem>::from(active_menu_item);
+                    let delete_active_position: usize = From::<MenuItem>::from(app.active_menu_item);
                     if delete_active_position == monitor_position {
-                        complete_task(&mut tasks_list_state);
+                        if let Some(task) = app.selected_synced_task() {
+                            let id = task.id;
+                            let (mutation, action) = if task.status().is_closed() {
+                                (Mutation::ReopenTask { id }, "Reabrir task")
+                            } else {
+                                (Mutation::CompleteTask { id }, "Concluir task")
+                            };
+                            match app.queue(mutation) {
+                                Ok(_) => request_sync(&mut app, &runtime, client, &sync_tx),
+                                Err(e) => app.report(action, &e),
+                            }
+                        }
                     }
                 }
                 KeyCode::Char('d') => {
-                    let delete_active_position: usize = From::<MenuItem>::from(active_menu_item);
+                    let delete_active_position: usize = From::<MenuItem>::from(app.active_menu_item);
                     if delete_active_position == monitor_position {
-                        delete_task(&mut tasks_list_state);
+                        let confirm = app.selected_synced_task().map(|task| Confirm {
+                            title: String::from("Deletar task"),
+                            lines: vec![
+                                task.content_preview.clone(),
+                                format!("Projeto: {}", task.project),
+                                String::from("Esta ação não pode ser desfeita."),
+                            ],
+                            action: ConfirmAction::DeleteTask(task.id),
+                        });
+                        if let Some(confirm) = confirm {
+                            app.modal = Some(Modal::Confirm(confirm));
+                        }
+                    } else if delete_active_position == outbox_position {
+                        if let Some(entry) = app.outbox_list_state.selected().and_then(|selected| app.outbox.entries.get(selected)) {
+                            app.modal = Some(Modal::Confirm(Confirm {
+                                title: String::from("Descartar operação"),
+                                lines: vec![
+                                    describe_mutation(&entry.mutation),
+                                    String::from("A alteração nunca chegará ao servidor."),
+                                ],
+                                action: ConfirmAction::DiscardOutboxEntry(entry.id),
+                            }));
+                        }
+                    }
+                }
+                KeyCode::Char('R') => {
+                    let sort_active_position: usize = From::<MenuItem>::from(app.active_menu_item);
+                    if sort_active_position == monitor_position {
+                        let sort = TaskSort { descending: !app.task_sort.descending, ..app.task_sort };
+                        if let Err(e) = app.set_task_sort(sort) {
+                            app.report("Salvar a ordenação", &e);
+                        }
+                    }
+                }
+                KeyCode::Char('r') => {
+                    let retry_active_position: usize = From::<MenuItem>::from(app.active_menu_item);
+                    if retry_active_position == monitor_position {
+                        let sort = TaskSort { field: app.task_sort.field.next(), ..app.task_sort };
+                        if let Err(e) = app.set_task_sort(sort) {
+                            app.report("Salvar a ordenação", &e);
+                        }
+                    } else if retry_active_position == outbox_position {
+                        match app.retry_outbox_entry() {
+                            Ok(()) => request_sync(&mut app, &runtime, client, &sync_tx),
+                            Err(e) => app.report("Reenviar operação", &e),
+                        }
+                    }
+                }
+                KeyCode::PageDown => {
+                    let scroll_active_position: usize = From::<MenuItem>::from(app.active_menu_item);
+                    if scroll_active_position == monitor_position {
+                        let lines = app.selected_task().map(|task| thread_lines(&app.thread(task)).len()).unwrap_or(0);
+                        app.thread_scroll = (app.thread_scroll + THREAD_PAGE).min(lines.saturating_sub(1) as u16);
+                    } else if scroll_active_position == calendar_position {
+                        app.calendar.move_months(1);
+                    }
+                }
+                KeyCode::PageUp => {
+                    let scroll_active_position: usize = From::<MenuItem>::from(app.active_menu_item);
+                    if scroll_active_position == monitor_position {
+                        app.thread_scroll = app.thread_scroll.saturating_sub(THREAD_PAGE);
+                    } else if scroll_active_position == calendar_position {
+                        app.calendar.move_months(-1);
+                    }
+                }
+                KeyCode::Right => {
+                    let right_active_position: usize = From::<MenuItem>::from(app.active_menu_item);
+                    if right_active_position == calendar_position {
+                        app.calendar.move_days(1);
+                    }
+                }
+                KeyCode::Left => {
+                    let left_active_position: usize = From::<MenuItem>::from(app.active_menu_item);
+                    if left_active_position == calendar_position {
+                        app.calendar.move_days(-1);
+                    }
+                }
+                KeyCode::Enter => {
+                    let enter_active_position: usize = From::<MenuItem>::from(app.active_menu_item);
+                    if enter_active_position == calendar_position {
+                        let day = app.calendar.selected;
+                        let due = tasks_due_on(&app.all_tasks, day);
+                        let options: Vec<String> =
+                            due.iter().map(|task| format!("{} — {}", task.content_preview, task.project)).collect();
+                        if options.is_empty() {
+                            app.set_status(StatusKind::Info, format!("Nenhuma entrega em {}", day.format(DATE_FORMAT)));
+                        } else {
+                            let title = format!("Entregas em {}", day.format(DATE_FORMAT));
+                            let ids = due.iter().map(|task| task.id).collect();
+                            app.modal = Some(Modal::Picker(Picker::new(&title, options, 0, PickerAction::CalendarDay(ids))));
+                        }
                     }
                 }
                 KeyCode::Down => {
-                    let down_active_position: usize = From::<MenuItem>::from(active_menu_item);
+                    let down_active_position: usize = From::<MenuItem>::from(app.active_menu_item);
                     if down_active_position == monitor_position {
-                        if let Some(selected) = tasks_list_state.selected() {
-                            let amount_tasks = read_db_task().expect("can fetch task list").len();
-                            if selected >= amount_tasks - 1 {
-                                tasks_list_state.select(Some(0));
-                            } else {
-                                tasks_list_state.select(Some(selected + 1));
-                            }
-                        }
+                        select_next(&mut app.tasks_list_state, app.tasks.len());
+                        app.thread_scroll = 0;
                     } else if down_active_position == comments_position {
-                        if let Some(selected) = comments_list_state.selected() {
-                            let amount_comments = read_db_comment().expect("can fetch task list").len();
-                            if selected >= amount_comments - 1 {
-                                comments_list_state.select(Some(0));
-                            } else {
-                                comments_list_state.select(Some(selected + 1));
-                            }
-                        }
+                        select_next(&mut app.comments_list_state, app.comments.len());
                     } else if down_active_position == projects_position {
-                        if let Some(selected) = projects_list_state.selected() {
-                            let amount_comments = read_db_project().expect("can fetch task list").len();
-                            if selected >= amount_comments - 1 {
-                                projects_list_state.select(Some(0));
-                            } else {
-                                projects_list_state.select(Some(selected + 1));
-                            }
-                        }
+                        select_next(&mut app.projects_list_state, app.projects.len());
+                    } else if down_active_position == outbox_position {
+                        select_next(&mut app.outbox_list_state, app.outbox.entries.len());
+                    } else if down_active_position == calendar_position {
+                        app.calendar.move_days(7);
                     }
                 }
                 KeyCode::Up => {
-                    let up_active_position: usize = From::<MenuItem>::from(active_menu_item);
+                    let up_active_position: usize = From::<MenuItem>::from(app.active_menu_item);
                     if up_active_position == monitor_position {
-                        if let Some(selected) = tasks_list_state.selected() {
-                            let amount_tasks = read_db_task().expect("can fetch task list").len();
-                            if selected > 0 {
-                                tasks_list_state.select(Some(selected - 1));
-                            } else {
-                                tasks_list_state.select(Some(amount_tasks - 1));
-                            }
-                        }
+                        select_previous(&mut app.tasks_list_state, app.tasks.len());
+                        app.thread_scroll = 0;
                     } else if up_active_position == comments_position {
-                        if let Some(selected) = comments_list_state.selected() {
-                            let amount_comments = read_db_comment().expect("can fetch task list").len();
-                            if selected > 0 {
-                                comments_list_state.select(Some(selected - 1));
-                            } else {
-                                comments_list_state.select(Some(amount_comments - 1));
-                            }
-                        }
+                        select_previous(&mut app.comments_list_state, app.comments.len());
                     } else if up_active_position == projects_position {
-                        if let Some(selected) = projects_list_state.selected() {
-                            let amount_comments = read_db_project().expect("can fetch task list").len();
-                            if selected > 0 {
-                                projects_list_state.select(Some(selected - 1));
-                            } else {
-                                projects_list_state.select(Some(amount_comments - 1));
-                            }
-                        }
+                        select_previous(&mut app.projects_list_state, app.projects.len());
+                    } else if up_active_position == outbox_position {
+                        select_previous(&mut app.outbox_list_state, app.outbox.entries.len());
+                    } else if up_active_position == calendar_position {
+                        app.calendar.move_days(-7);
                     }
                 }
                 _ => {}
             },
-            Event::Tick => {}
+            (Event::Tick, _) => {
+                if let Err(e) = app.reload() {
+                    app.report("Carregar dados locais", &e);
+                }
+                // Keeps an open search in step with the lists the reload may have changed.
+                if let Some(Modal::Search(_)) = app.modal {
+                    if let Some(Modal::Search(mut search)) = app.modal.take() {
+                        search.refresh(&app);
+                        app.modal = Some(Modal::Search(search));
+                    }
+                }
+            }
+            (Event::Sync(event), _) => app.on_sync_event(event),
+            (Event::SyncDone(report), _) => {
+                if let Err(e) = app.apply_sync(report) {
+                    app.report("Salvar operações pendentes", &e);
+                }
+            }
+            (Event::Submitted(mutation, result), Some(client)) => on_submitted(&mut app, mutation, result, &runtime, client, &sync_tx),
+            (Event::Submitted(..), None) => {}
         }
     }
 
     Ok(())
 }
 
-fn render_error<'a>(title: &'a str, msg: &'a str, msg2: &'a str) -> Paragraph<'a> {
-    let error = Paragraph::new(vec![
-        Spans::from(vec![Span::raw("")]),
+/// Keys go to the open modal only, so nothing behind it reacts.
+fn on_modal_key(app: &mut App, event: KeyEvent, runtime: &tokio::runtime::Runtime, client: &Client, tx: &mpsc::Sender<Event<KeyEvent>>) {
+    match app.modal.as_mut() {
+        Some(Modal::Confirm(_)) => match event.code {
+            KeyCode::Esc | KeyCode::Char('n') => app.modal = None,
+            KeyCode::Enter => {
+                if let Some(Modal::Confirm(confirm)) = app.modal.take() {
+                    run_confirmed(app, confirm.action, runtime, client, tx);
+                }
+            }
+            _ => {}
+        },
+        Some(Modal::Search(_)) => {
+            if let Some(Modal::Search(search)) = app.modal.take() {
+                app.modal = on_search_key(app, search, event).map(Modal::Search);
+            }
+        }
+        Some(Modal::Picker(picker)) => match event.code {
+            KeyCode::Esc => app.modal = None,
+            KeyCode::Down => select_next(&mut picker.state, picker.options.len()),
+            KeyCode::Up => select_previous(&mut picker.state, picker.options.len()),
+            KeyCode::Enter => {
+                if let Some(Modal::Picker(picker)) = app.modal.take() {
+                    run_picked(app, picker);
+                }
+            }
+            _ => {}
+        },
+        Some(Modal::Form(form)) => match form.on_key(event) {
+            FormKey::Cancel => app.modal = None,
+            FormKey::Submit => {
+                if let Some(Modal::Form(form)) = app.modal.take() {
+                    submit_form(app, form, runtime, client, tx);
+                }
+            }
+            FormKey::Continue => {}
+        },
+        None => {}
+    }
+}
+
+/// Returns the search while it stays open.
+fn on_search_key(app: &mut App, mut search: Search, event: KeyEvent) -> Option<Search> {
+    match event.code {
+        KeyCode::Esc => return None,
+        KeyCode::Enter => {
+            if let Some(result) = search.selected() {
+                jump_to(app, result.target);
+            }
+            return None;
+        }
+        KeyCode::Down => select_next(&mut search.state, search.results.len()),
+        KeyCode::Up => select_previous(&mut search.state, search.results.len()),
+        KeyCode::Backspace => {
+            search.query.pop();
+            search.update(app);
+        }
+        KeyCode::Char(c) if !event.modifiers.contains(KeyModifiers::CONTROL) => {
+            search.query.push(c);
+            search.update(app);
+        }
+        _ => {}
+    }
+    Some(search)
+}
+
+/// Opens the tab of a search result with it selected.
+fn jump_to(app: &mut App, target: SearchTarget) {
+    match target {
+        SearchTarget::Task(id) => {
+            let closed = app.all_tasks.iter().find(|task| task.id == id).map(|task| task.status().is_closed());
+            if closed.map_or(false, |closed| closed != app.task_filter.closed) {
+                app.toggle_closed_view();
+            }
+            if !app.tasks.iter().any(|task| task.id == id) {
+                app.set_status(StatusKind::Info, String::from("Filtro de projeto removido para mostrar a task"));
+                if let Err(e) = app.set_project_filter(None) {
+                    app.report("Salvar o filtro", &e);
+                }
+            }
+            if let Some(index) = app.tasks.iter().position(|task| task.id == id) {
+                app.tasks_list_state.select(Some(index));
+                app.thread_scroll = 0;
+                app.active_menu_item = MenuItem::Monitor;
+            }
+        }
+        SearchTarget::Comment(id) => {
+            if let Some(index) = app.comments.iter().position(|comment| comment.id == id) {
+                app.comments_list_state.select(Some(index));
+                app.active_menu_item = MenuItem::Comments;
+            }
+        }
+        SearchTarget::Project(id) => {
+            if let Some(index) = app.projects.iter().position(|project| project.id == id) {
+                app.projects_list_state.select(Some(index));
+                app.active_menu_item = MenuItem::Projects;
+            }
+        }
+    }
+}
+
+fn run_picked(app: &mut App, picker: Picker) {
+    let selected = match picker.selected() {
+        Some(selected) => selected,
+        None => return,
+    };
+    match picker.action {
+        PickerAction::ProjectFilter => {
+            let project = if selected == 0 { None } else { Some(picker.options[selected].clone()) };
+            app.thread_scroll = 0;
+            if let Err(e) = app.set_project_filter(project) {
+                app.report("Salvar o filtro", &e);
+            }
+        }
+        PickerAction::CalendarDay(ids) => {
+            if let Some(&id) = ids.get(selected) {
+                jump_to(app, SearchTarget::Task(id));
+            }
+        }
+    }
+}
+
+/// Validates and queues the form's change, or reopens it with the errors.
+fn submit_form(app: &mut App, mut form: Form, runtime: &tokio::runtime::Runtime, client: &Client, tx: &mpsc::Sender<Event<KeyEvent>>) {
+    match form.action.clone() {
+        FormAction::CreateTask => match read_new_task(&mut form) {
+            Some(task) => match app.queue(Mutation::CreateTask { task }) {
+                Ok(entry_id) => {
+                    // Until the server assigns an id, the new task goes by its entry's placeholder.
+                    let index = app.tasks.iter().position(|listed| listed.id == unsynced_id(entry_id));
+                    if index.is_some() {
+                        app.tasks_list_state.select(index);
+                    }
+                    request_sync(app, runtime, client, tx);
+                }
+                Err(e) => app.report("Criar task", &e),
+            },
+            None => app.modal = Some(Modal::Form(form)),
+        },
+        FormAction::CreateComment { task_id, task_preview } => match read_new_comment(&mut form, task_id, &task_preview) {
+            Some(comment) => match app.queue(Mutation::CreateComment { comment }) {
+                Ok(_) => request_sync(app, runtime, client, tx),
+                Err(e) => app.report("Comentar", &e),
+            },
+            None => app.modal = Some(Modal::Form(form)),
+        },
+        FormAction::EditTask(id) => match read_task_patch(&mut form) {
+            Some(patch) if patch.is_empty() => app.set_status(StatusKind::Info, String::from("Nenhuma alteração")),
+            Some(patch) => {
+                form.submitting = true;
+                app.modal = Some(Modal::Form(form));
+                submit(runtime, client, tx, Mutation::UpdateTask { id, patch });
+            }
+            None => app.modal = Some(Modal::Form(form)),
+        },
+        FormAction::CreateProject => match read_new_project(&mut form) {
+            Some(project) => {
+                form.submitting = true;
+                app.modal = Some(Modal::Form(form));
+                submit(runtime, client, tx, Mutation::CreateProject { project });
+            }
+            None => app.modal = Some(Modal::Form(form)),
+        },
+        FormAction::EditProject(id) => match read_project_patch(&mut form) {
+            Some(patch) if patch.is_empty() => app.set_status(StatusKind::Info, String::from("Nenhuma alteração")),
+            Some(patch) => {
+                form.submitting = true;
+                app.modal = Some(Modal::Form(form));
+                submit(runtime, client, tx, Mutation::UpdateProject { id, patch });
+            }
+            None => app.modal = Some(Modal::Form(form)),
+        },
+    }
+}
+
+/// Sends `mutation` right away so validation errors can be shown in the form that made it.
+fn submit(runtime: &tokio::runtime::Runtime, client: &Client, tx: &mpsc::Sender<Event<KeyEvent>>, mutation: Mutation) {
+    let client = client.clone();
+    let tx = tx.clone();
+    runtime.spawn(async move {
+        let result = mutation.send(&client).await;
+        let _ = tx.send(Event::Submitted(mutation, result));
+    });
+}
+
+/// Closes the form on success, puts validation errors next to its fields, and
+/// queues the change in the outbox when the server can't be reached.
+fn on_submitted(app: &mut App, mutation: Mutation, result: Result<(), Error>, runtime: &tokio::runtime::Runtime, client: &Client, tx: &mpsc::Sender<Event<KeyEvent>>) {
+    let open_form = match app.modal.as_mut() {
+        Some(Modal::Form(form)) if form.submitting => Some(form),
+        _ => None,
+    };
+    match result {
+        Ok(()) => {
+            if open_form.is_some() {
+                app.modal = None;
+            }
+            app.set_status(StatusKind::Success, format!("{}: salvo", describe_mutation(&mutation)));
+            request_sync(app, runtime, client, tx);
+        }
+        Err(Error::ValidationError { fields, .. }) if open_form.is_some() => {
+            if let Some(form) = open_form {
+                form.set_server_errors(&fields);
+            }
+        }
+        Err(e) if e.is_transient() => {
+            if open_form.is_some() {
+                app.modal = None;
+            }
+            match app.queue(mutation) {
+                Ok(_) => app.set_status(
+                    StatusKind::Info,
+                    String::from("Servidor indisponível, alteração guardada em Operações"),
+                ),
+                Err(e) => app.report("Guardar alteração", &e),
+            }
+        }
+        Err(e) => {
+            if let Some(form) = open_form {
+                form.submitting = false;
+                form.error = Some(e.to_string());
+            }
+            app.report(&describe_mutation(&mutation), &e);
+        }
+    }
+}
+
+fn run_confirmed(app: &mut App, action: ConfirmAction, runtime: &tokio::runtime::Runtime, client: &Client, tx: &mpsc::Sender<Event<KeyEvent>>) {
+    match action {
+        ConfirmAction::DeleteTask(id) => match app.queue(Mutation::DeleteTask { id }) {
+            Ok(_) => request_sync(app, runtime, client, tx),
+            Err(e) => app.report("Deletar task", &e),
+        },
+        ConfirmAction::DiscardOutboxEntry(id) => {
+            if let Err(e) = app.discard_outbox_entry(id) {
+                app.report("Descartar operação", &e);
+            }
+        }
+    }
+}
+
+/// Starts a sync unless one is already running.
+fn request_sync(app: &mut App, runtime: &tokio::runtime::Runtime, client: &Client, tx: &mpsc::Sender<Event<KeyEvent>>) {
+    if app.syncing {
+        return;
+    }
+    let dir = match &app.data_dir {
+        Some(dir) => dir.clone(),
+        None => return,
+    };
+    let lock = match StoreLock::try_exclusive(&dir.join(LOCK_FILE)) {
+        Ok(Some(lock)) => lock,
+        Ok(None) => {
+            app.set_status(StatusKind::Info, String::from("Outra sincronização já está em andamento"));
+            return;
+        }
+        Err(e) => {
+            app.report("Bloquear o banco local", &e);
+            return;
+        }
+    };
+    // Replays what the store holds rather than `app.outbox`, which may still list
+    // entries another process has already sent.
+    let opened = SqliteStore::open(&dir.join(DB_FILE))
+        .and_then(|store| Outbox::load(&store).map(|outbox| (store, outbox)));
+    match opened {
+        Ok((store, outbox)) => {
+            app.syncing = true;
+            start_sync(runtime, client, tx, store, lock, outbox.pending());
+        }
+        Err(e) => app.report("Abrir o banco local", &e),
+    }
+}
+
+/// Runs a full sync on the runtime, reporting back through the UI event channel.
+///
+/// The sync gets its own connection to the store so the UI can keep reading meanwhile,
+/// and holds `lock` until it is done.
+fn start_sync(runtime: &tokio::runtime::Runtime, client: &Client, tx: &mpsc::Sender<Event<KeyEvent>>, mut store: SqliteStore, lock: StoreLock, outbox: Vec<OutboxEntry>) {
+    let client = client.clone();
+    let progress_tx = tx.clone();
+    let done_tx = tx.clone();
+    runtime.spawn(async move {
+        let report = sync::sync_all(&client, &mut store, &outbox, move |event| {
+            let _ = progress_tx.send(Event::Sync(event));
+        })
+        .await;
+        drop(lock);
+        let _ = done_tx.send(Event::SyncDone(report));
+    });
+}
+
+fn render_status<'a>(status: Option<&Status>) -> Paragraph<'a> {
+    let (text, color) = match status {
+        Some(status) => (
+            status.message.clone(),
+            match status.kind {
+                StatusKind::Info => Color::Gray,
+                StatusKind::Success => Color::Green,
+                StatusKind::Failure => Color::Red,
+            },
+        ),
+        None => (String::new(), Color::Gray),
+    };
+    Paragraph::new(Spans::from(vec![Span::styled(text, Style::default().fg(color))]))
+}
+
+fn render_error<'a>(failure: Option<&Failure>) -> Paragraph<'a> {
+    let failure = match failure {
+        Some(failure) => failure,
+        None => {
+            return Paragraph::new("Nenhum erro registrado")
+                .alignment(Alignment::Center)
+                .block(
+                    Block::default()
+                        .borders(Borders::ALL)
+                        .style(Style::default().fg(Color::White))
+                        .title("Erro")
+                        .border_type(BorderType::Rounded),
+                );
+        }
+    };
+    let mut lines = vec![
         Spans::from(vec![Span::raw("")]),
         Spans::from(vec![Span::styled(
-            title,
-            Style::default().fg(Color::Red).add_modifier(Modifier::RAPID_BLINK),
+            format!("Falha ao {}", failure.action.to_lowercase()),
+            Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
         )]),
         Spans::from(vec![Span::raw("")]),
-        Spans::from(vec![Span::styled(
-            msg,
-            Style::default().fg(Color::Red),
-        )]),
-        Spans::from(vec![Span::styled(
-            msg2,
-            Style::default().fg(Color::Red),
-        )]),
-    ])
+        Spans::from(vec![
+            Span::styled("Causa: ", Style::default().fg(Color::LightCyan)),
+            Span::styled(failure.cause.clone(), Style::default().fg(Color::Red)),
+        ]),
+    ];
+    if let Some(location) = &failure.location {
+        lines.push(Spans::from(vec![
+            Span::styled("Local: ", Style::default().fg(Color::LightCyan)),
+            Span::raw(location.clone()),
+        ]));
+    }
+    lines.push(Spans::from(vec![Span::raw("")]));
+    lines.push(Spans::from(vec![
+        Span::styled("Sugestão: ", Style::default().fg(Color::LightCyan)),
+        Span::raw(failure.hint),
+    ]));
+    let error = Paragraph::new(lines)
         .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
         .block(
             Block::default()
                 .borders(Borders::ALL)
@@ -366,16 +958,18 @@ fn render_options<'a>(text: &'a str) -> Paragraph<'a> {
     let options = Paragraph::new(text)
         .style(Style::default().fg(Color::White))
         .alignment(Alignment::Center)
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .style(Style::default().fg(Color::White))
-                .title("Opções")
-                .border_type(BorderType::Rounded),
-        );
+        .block(options_block(Spans::from("Opções")));
     options
 }
 
+fn options_block<'a>(title: Spans<'a>) -> Block<'a> {
+    Block::default()
+        .borders(Borders::ALL)
+        .style(Style::default().fg(Color::White))
+        .title(title)
+        .border_type(BorderType::Rounded)
+}
+
 fn render_home<'a>() -> Paragraph<'a> {
     let home = Paragraph::new(vec![
         Spans::from(vec![Span::styled(
@@ -417,47 +1011,93 @@ fn render_license<'a>() -> Paragraph<'a> {
     license
 }
 
-fn render_monitor<'a>(tasks_list_state: &ListState) -> (List<'a>, Table<'a>) {
+/// "Monitor", followed by the active filters and sort.
+fn monitor_title(filter: &TaskFilter, sort: &TaskSort) -> String {
+    let mut parts = filter.describe();
+    parts.push(sort.describe());
+    format!("Monitor — {}", parts.join(" | "))
+}
+
+/// Why the Monitor list is empty: nothing synced yet, or a project filter or view
+/// hiding every task, with the key that shows them again.
+fn monitor_empty_state(filter: &TaskFilter, has_tasks: bool) -> String {
+    match (&filter.project, filter.closed) {
+        _ if !has_tasks => String::from("Nenhuma task — pressione u para sincronizar"),
+        (Some(project), true) => format!(
+            "Nenhuma task concluída em {} — pressione P para trocar de projeto ou v para ver as abertas",
+            project
+        ),
+        (Some(project), false) => format!(
+            "Nenhuma task aberta em {} — pressione P para trocar de projeto ou v para ver as concluídas",
+            project
+        ),
+        (None, true) => String::from("Nenhuma task concluída — pressione v para ver as abertas"),
+        (None, false) => String::from("Nenhuma task aberta — pressione v para ver as concluídas"),
+    }
+}
+
+/// Color and badge of a task in the Monitor list.
+fn deadline_style(deadline: Deadline) -> (Style, &'static str) {
+    match deadline {
+        Deadline::Overdue => (Style::default().fg(Color::Red).add_modifier(Modifier::BOLD), "! "),
+        Deadline::DueSoon => (Style::default().fg(Color::Yellow), "~ "),
+        Deadline::Done => (Style::default().fg(Color::Green).add_modifier(Modifier::DIM), "✓ "),
+        Deadline::Cancelled => (Style::default().fg(Color::DarkGray), "× "),
+        Deadline::OnTrack => (Style::default(), "  "),
+    }
+}
+
+fn deadline_legend<'a>() -> Spans<'a> {
+    let mut spans = vec![Span::raw("Opções — ")];
+    for (deadline, label) in &[
+        (Deadline::Overdue, "Atrasada"),
+        (Deadline::DueSoon, "Vence em breve"),
+        (Deadline::Done, "Concluída"),
+        (Deadline::Cancelled, "Cancelada"),
+    ] {
+        let (style, badge) = deadline_style(*deadline);
+        spans.push(Span::styled(format!("{}{} ", badge, label), style));
+    }
+    Spans::from(spans)
+}
+
+fn render_monitor<'a>(tasks_list: &[Task], tasks_list_state: &ListState, title: String, due_soon: chrono::Duration, empty: &'a str) -> (List<'a>, Table<'a>) {
     let tasks = Block::default()
         .borders(Borders::ALL)
         .style(Style::default().fg(Color::White))
-        .title("Monitor")
+        .title(title)
         .border_type(BorderType::Rounded);
 
-    let tasks_list = read_db_task().expect("can fetch task list");
+    let now = Utc::now();
     let items: Vec<_> = tasks_list
         .iter()
         .map(|task| {
+            let (style, badge) = deadline_style(task.deadline(now, due_soon));
             ListItem::new(Spans::from(vec![Span::styled(
-                task.content_preview.clone(),
-                Style::default(),
+                format!("{}{}", badge, task.content_preview),
+                style,
             )]))
         })
         .collect();
 
-    let selected_task = tasks_list
-        .get(
-            tasks_list_state
-                .selected()
-                .expect("there is always a selected task"),
-        )
-        .expect("exists")
-        .clone();
+    let rows = match tasks_list_state.selected().and_then(|selected| tasks_list.get(selected)) {
+        Some(task) => vec![Row::new(vec![
+            Cell::from(Span::raw(task.project.clone())),
+            Cell::from(Span::raw(task.content.to_string())),
+            Cell::from(Span::raw(task.begin_date.to_string())),
+            Cell::from(Span::raw(task.end_date.to_string())),
+            Cell::from(Span::raw(status_label(task.status()))),
+        ])],
+        None => vec![Row::new(vec![Cell::from(Span::raw(empty))])],
+    };
 
-    let list = List::new(items).block(tasks).highlight_style(
+    let list = List::new(with_empty_state(items, empty)).block(tasks).highlight_style(
         Style::default()
             .bg(Color::White)
             .fg(Color::Black)
             .add_modifier(Modifier::BOLD),
     );
-    let task_detail = Table::new(vec![
-        Row::new(vec![
-            Cell::from(Span::raw(selected_task.project)),
-            Cell::from(Span::raw(selected_task.content.to_string())),
-            Cell::from(Span::raw(selected_task.begin_date.to_string())),
-            Cell::from(Span::raw(selected_task.end_date.to_string())),
-        ])
-    ])
+    let task_detail = Table::new(rows)
         .header(Row::new(vec![
             Cell::from(Span::styled(
                 "Projeto",
@@ -475,6 +1115,10 @@ fn render_monitor<'a>(tasks_list_state: &ListState) -> (List<'a>, Table<'a>) {
                 "Entrega",
                 Style::default().add_modifier(Modifier::BOLD),
             )),
+            Cell::from(Span::styled(
+                "Situação",
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
         ]))
         .block(
             Block::default()
@@ -485,22 +1129,118 @@ fn render_monitor<'a>(tasks_list_state: &ListState) -> (List<'a>, Table<'a>) {
         )
         .widths(&[
             Constraint::Percentage(10),
-            Constraint::Percentage(45),
-            Constraint::Percentage(25),
-            Constraint::Percentage(25),
+            Constraint::Percentage(40),
+            Constraint::Percentage(20),
+            Constraint::Percentage(20),
+            Constraint::Percentage(10),
         ]);
 
     (list, task_detail)
 }
 
-fn render_comments<'a>(comments_list_state: &ListState) -> (List<'a>, Table<'a>) {
+/// Task titles listed in a day of the Agenda; the rest are only counted.
+const TITLES_PER_DAY: usize = 2;
+
+fn render_calendar<'a>(calendar: &Calendar, tasks: &[Task], due_soon: chrono::Duration) -> Table<'a> {
+    let now = Utc::now();
+    let today = now.naive_utc().date();
+    let rows: Vec<_> = calendar
+        .weeks()
+        .iter()
+        .map(|week| {
+            let cells: Vec<_> = week
+                .iter()
+                .map(|day| {
+                    let due = tasks_due_on(tasks, *day);
+                    let mut style = if day.month() == calendar.selected.month() {
+                        Style::default().add_modifier(Modifier::BOLD)
+                    } else {
+                        Style::default().fg(Color::DarkGray)
+                    };
+                    if *day == today {
+                        style = style.add_modifier(Modifier::UNDERLINED);
+                    }
+                    if *day == calendar.selected {
+                        style = style.bg(Color::White).fg(Color::Black);
+                    }
+                    let header = match due.len() {
+                        0 => format!("{:>2}", day.day()),
+                        1 => format!("{:>2} · 1 task", day.day()),
+                        count => format!("{:>2} · {} tasks", day.day(), count),
+                    };
+                    let mut lines = vec![Spans::from(Span::styled(header, style))];
+                    for task in due.iter().take(TITLES_PER_DAY) {
+                        let (style, badge) = deadline_style(task.deadline(now, due_soon));
+                        lines.push(Spans::from(Span::styled(format!("{}{}", badge, task.content_preview), style)));
+                    }
+                    Cell::from(Text::from(lines))
+                })
+                .collect();
+            Row::new(cells).height(1 + TITLES_PER_DAY as u16)
+        })
+        .collect();
+
+    let header = WEEKDAYS
+        .iter()
+        .map(|weekday| Cell::from(Span::styled(*weekday, Style::default().add_modifier(Modifier::BOLD))))
+        .collect::<Vec<_>>();
+    Table::new(rows)
+        .header(Row::new(header))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::White))
+                .title(format!("Agenda — {}", calendar.month_label()))
+                .border_type(BorderType::Rounded),
+        )
+        .widths(&[Constraint::Ratio(1, 7); 7])
+        .column_spacing(1)
+}
+
+/// Lines scrolled by PageUp/PageDown in the comment thread.
+const THREAD_PAGE: u16 = 5;
+
+fn thread_lines<'a>(thread: &[&Comment]) -> Vec<Spans<'a>> {
+    if thread.is_empty() {
+        return vec![Spans::from(vec![Span::styled(
+            "Nenhum comentário — pressione m para comentar",
+            Style::default().fg(Color::DarkGray),
+        )])];
+    }
+    let mut lines = Vec::new();
+    for comment in thread {
+        lines.push(Spans::from(vec![Span::styled(
+            comment.created_at.with_timezone(&Local).format("%d/%m/%Y %H:%M").to_string(),
+            Style::default().fg(Color::LightCyan).add_modifier(Modifier::BOLD),
+        )]));
+        for line in comment.content.lines() {
+            lines.push(Spans::from(vec![Span::raw(line.to_owned())]));
+        }
+        lines.push(Spans::from(vec![Span::raw("")]));
+    }
+    lines
+}
+
+fn render_thread<'a>(thread: &[&Comment], scroll: u16) -> Paragraph<'a> {
+    Paragraph::new(thread_lines(thread))
+        .wrap(Wrap { trim: false })
+        .scroll((scroll, 0))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::White))
+                .title(format!("Comentários ({})", thread.len()))
+                .border_type(BorderType::Rounded),
+        )
+}
+
+fn render_comments<'a>(comments_list: &[Comment], comments_list_state: &ListState, empty: &'a str) -> (List<'a>, Table<'a>) {
     let comments = Block::default()
         .borders(Borders::ALL)
         .style(Style::default().fg(Color::White))
         .title("Comentários")
         .border_type(BorderType::Rounded);
 
-    let comments_list = read_db_comment().expect("can fetch comments list");
     let items: Vec<_> = comments_list
         .iter()
         .map(|comment| {
@@ -511,27 +1251,21 @@ fn render_comments<'a>(comments_list_state: &ListState) -> (List<'a>, Table<'a>)
         })
         .collect();
 
-    let selected_comment = comments_list
-        .get(
-            comments_list_state
-                .selected()
-                .expect("there is always a selected comment"),
-        )
-        .expect("exists")
-        .clone();
+    let rows = match comments_list_state.selected().and_then(|selected| comments_list.get(selected)) {
+        Some(comment) => vec![Row::new(vec![
+            Cell::from(Span::raw(comment.content.to_string())),
+            Cell::from(Span::raw(comment.created_at.to_string())),
+        ])],
+        None => vec![Row::new(vec![Cell::from(Span::raw(empty))])],
+    };
 
-    let list = List::new(items).block(comments).highlight_style(
+    let list = List::new(with_empty_state(items, empty)).block(comments).highlight_style(
         Style::default()
             .bg(Color::White)
             .fg(Color::Black)
             .add_modifier(Modifier::BOLD),
     );
-    let comment_detail = Table::new(vec![
-        Row::new(vec![
-            Cell::from(Span::raw(selected_comment.content.to_string())),
-            Cell::from(Span::raw(selected_comment.created_at.to_string())),
-        ])
-    ])
+    let comment_detail = Table::new(rows)
         .header(Row::new(vec![
             Cell::from(Span::styled(
                 "Comentário",
@@ -557,14 +1291,13 @@ fn render_comments<'a>(comments_list_state: &ListState) -> (List<'a>, Table<'a>)
     (list, comment_detail)
 }
 
-fn render_projects<'a>(projects_list_state: &ListState) -> (List<'a>, Table<'a>) {
+fn render_projects<'a>(projects_list: &[Project], projects_list_state: &ListState, masked: bool, empty: &'a str) -> (List<'a>, Table<'a>) {
     let projects = Block::default()
         .borders(Borders::ALL)
         .style(Style::default().fg(Color::White))
         .title("Projetos")
         .border_type(BorderType::Rounded);
 
-    let projects_list = read_db_project().expect("can fetch projects list");
     let items: Vec<_> = projects_list
         .iter()
         .map(|project| {
@@ -575,29 +1308,24 @@ fn render_projects<'a>(projects_list_state: &ListState) -> (List<'a>, Table<'a>)
         })
         .collect();
 
-    let selected_project = projects_list
-        .get(
-            projects_list_state
-                .selected()
-                .expect("there is always a selected comment"),
-        )
-        .expect("exists")
-        .clone();
+    let selected = projects_list_state.selected().and_then(|selected| projects_list.get(selected));
+    let rows = match selected.map(|project| if masked { project.masked() } else { project.clone() }) {
+        Some(project) => vec![Row::new(vec![
+            Cell::from(Span::raw(project.customer_name.to_string())),
+            Cell::from(Span::raw(project.customer_document.to_string())),
+            Cell::from(Span::raw(project.customer_contact.to_string())),
+            Cell::from(Span::raw(project.created_at.to_string())),
+        ])],
+        None => vec![Row::new(vec![Cell::from(Span::raw(empty))])],
+    };
 
-    let list = List::new(items).block(projects).highlight_style(
+    let list = List::new(with_empty_state(items, empty)).block(projects).highlight_style(
         Style::default()
             .bg(Color::White)
             .fg(Color::Black)
             .add_modifier(Modifier::BOLD),
     );
-    let project_detail = Table::new(vec![
-        Row::new(vec![
-            Cell::from(Span::raw(selected_project.customer_name.to_string())),
-            Cell::from(Span::raw(selected_project.customer_document.to_string())),
-            Cell::from(Span::raw(selected_project.customer_contact.to_string())),
-            Cell::from(Span::raw(selected_project.created_at.to_string())),
-        ])
-    ])
+    let project_detail = Table::new(rows)
         .header(Row::new(vec![
             Cell::from(Span::styled(
                 "Cliente",
@@ -633,36 +1361,94 @@ fn render_projects<'a>(projects_list_state: &ListState) -> (List<'a>, Table<'a>)
     (list, project_detail)
 }
 
-fn read_db_task() -> Result<Vec<Task>, Error> {
-    let db_content = fs::read_to_string(TASK_PATH)?;
-    let parsed: Vec<Task> = serde_json::from_str(&db_content)?;
-    Ok(parsed)
-}
+/// Shows `message` in place of the items while the list is empty.
+/// Detail pane of a list filtered down to nothing by a search.
+const NOTHING_FOUND: &str = "Nada encontrado — pressione Esc para limpar a busca";
 
-fn read_db_comment() -> Result<Vec<Comment>, Error> {
-    let db_content = fs::read_to_string(COMMENT_PATH)?;
-    let parsed: Vec<Comment> = serde_json::from_str(&db_content)?;
-    Ok(parsed)
+fn with_empty_state<'a>(items: Vec<ListItem<'a>>, message: &'a str) -> Vec<ListItem<'a>> {
+    if items.is_empty() {
+        vec![ListItem::new(Span::styled(message, Style::default().fg(Color::DarkGray)))]
+    } else {
+        items
+    }
 }
 
-fn read_db_project() -> Result<Vec<Project>, Error> {
-    let db_content = fs::read_to_string(PROJECT_PATH)?;
-    let parsed: Vec<Project> = serde_json::from_str(&db_content)?;
-    Ok(parsed)
-}
+fn render_outbox<'a>(entries: &[OutboxEntry], outbox_list_state: &ListState) -> (List<'a>, Table<'a>) {
+    let outbox = Block::default()
+        .borders(Borders::ALL)
+        .style(Style::default().fg(Color::White))
+        .title("Operações")
+        .border_type(BorderType::Rounded);
 
-fn complete_task(tasks_list_state: &mut ListState) -> Result<(), Error> {
-    if let Some(mut selected) = tasks_list_state.selected() {
-        selected = selected + 1;
-        Command::new("./codeplan-task-control").args(&["-complete", &selected.to_string()]).stderr(Stdio::null()).spawn().expect("codeplan-task-control command failed to start");
-    }
-    Ok(())
+    let items: Vec<_> = entries
+        .iter()
+        .map(|entry| {
+            let style = match entry.error {
+                Some(_) => Style::default().fg(Color::Red),
+                None => Style::default(),
+            };
+            ListItem::new(Spans::from(vec![Span::styled(describe_mutation(&entry.mutation), style)]))
+        })
+        .collect();
+
+    let rows = match outbox_list_state.selected().and_then(|selected| entries.get(selected)) {
+        Some(entry) => vec![Row::new(vec![
+            Cell::from(Span::raw(describe_mutation(&entry.mutation))),
+            Cell::from(Span::raw(entry.created_at.to_string())),
+            Cell::from(Span::raw(match &entry.error {
+                Some(e) => format!("Recusada: {}", e),
+                None => "Aguardando envio".to_string(),
+            })),
+        ])],
+        None => vec![Row::new(vec![Cell::from(Span::raw("Nenhuma operação pendente"))])],
+    };
+
+    let list = List::new(with_empty_state(items, "Nenhuma operação pendente")).block(outbox).highlight_style(
+        Style::default()
+            .bg(Color::White)
+            .fg(Color::Black)
+            .add_modifier(Modifier::BOLD),
+    );
+    let entry_detail = Table::new(rows)
+        .header(Row::new(vec![
+            Cell::from(Span::styled(
+                "Operação",
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Cell::from(Span::styled(
+                "Criada em",
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Cell::from(Span::styled(
+                "Situação",
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+        ]))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::White))
+                .title("Detalhes")
+                .border_type(BorderType::Rounded),
+        )
+        .widths(&[
+            Constraint::Percentage(30),
+            Constraint::Percentage(25),
+            Constraint::Percentage(45),
+        ]);
+
+    (list, entry_detail)
 }
 
-fn delete_task(tasks_list_state: &mut ListState) -> Result<(), Error> {
-    if let Some(mut selected) = tasks_list_state.selected() {
-        selected = selected + 1;
-        Command::new("./codeplan-task-control").args(&["-delete", &selected.to_string()]).stderr(Stdio::null()).spawn().expect("codeplan-task-control command failed to start");
+fn describe_mutation(mutation: &Mutation) -> String {
+    match mutation {
+        Mutation::CreateTask { task } => format!("Criar task {}", task.content_preview),
+        Mutation::CompleteTask { id } => format!("Concluir task #{}", id),
+        Mutation::ReopenTask { id } => format!("Reabrir task #{}", id),
+        Mutation::DeleteTask { id } => format!("Deletar task #{}", id),
+        Mutation::UpdateTask { id, .. } => format!("Editar task #{}", id),
+        Mutation::CreateComment { comment } => format!("Comentar em {}", comment.task_preview),
+        Mutation::CreateProject { project } => format!("Criar projeto {}", project.name),
+        Mutation::UpdateProject { id, .. } => format!("Editar projeto #{}", id),
     }
-    Ok(())
 }
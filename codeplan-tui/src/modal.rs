@@ -0,0 +1,140 @@
+use tui::{
+    layout::{Alignment, Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, List, ListItem, ListState, Paragraph, Wrap},
+};
+
+use crate::form::Form;
+use crate::search::Search;
+
+/// A popup drawn over the tabs, or the filter of a scoped search; while one is open it receives every key.
+pub enum Modal {
+    Confirm(Confirm),
+    Form(Form),
+    Picker(Picker),
+    Search(Search),
+}
+
+/// Yes/no question guarding a destructive action.
+pub struct Confirm {
+    pub title: String,
+    pub lines: Vec<String>,
+    pub action: ConfirmAction,
+}
+
+/// What to run once the user confirms.
+#[derive(Copy, Clone, Debug)]
+pub enum ConfirmAction {
+    DeleteTask(usize),
+    DiscardOutboxEntry(u64),
+}
+
+/// List of options to choose one from with Up/Down and Enter.
+pub struct Picker {
+    pub title: String,
+    pub options: Vec<String>,
+    pub state: ListState,
+    pub action: PickerAction,
+}
+
+impl Picker {
+    pub fn new(title: &str, options: Vec<String>, selected: usize, action: PickerAction) -> Picker {
+        let mut state = ListState::default();
+        state.select(Some(selected.min(options.len().saturating_sub(1))));
+        Picker {
+            title: title.to_owned(),
+            options,
+            state,
+            action,
+        }
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        self.state.selected().filter(|selected| *selected < self.options.len())
+    }
+}
+
+/// What the chosen option is for.
+#[derive(Clone, Debug)]
+pub enum PickerAction {
+    /// Option 0 clears the filter, the others are project names.
+    ProjectFilter,
+    /// Options are `calendar::tasks_due_on` the Agenda's selected day, these are their ids.
+    CalendarDay(Vec<usize>),
+}
+
+/// Rect of `percent_x` by `percent_y` of `area`, centered in it.
+pub fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let vertical = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_y) / 2),
+                Constraint::Percentage(percent_y),
+                Constraint::Percentage((100 - percent_y) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(area);
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints(
+            [
+                Constraint::Percentage((100 - percent_x) / 2),
+                Constraint::Percentage(percent_x),
+                Constraint::Percentage((100 - percent_x) / 2),
+            ]
+            .as_ref(),
+        )
+        .split(vertical[1])[1]
+}
+
+pub fn render_confirm<'a>(confirm: &'a Confirm) -> Paragraph<'a> {
+    let mut lines = vec![Spans::from(vec![Span::raw("")])];
+    for line in &confirm.lines {
+        lines.push(Spans::from(vec![Span::raw(line.as_str())]));
+    }
+    lines.push(Spans::from(vec![Span::raw("")]));
+    lines.push(Spans::from(vec![
+        Span::styled("(Enter) ", Style::default().fg(Color::LightCyan)),
+        Span::raw("Confirmar   "),
+        Span::styled("(Esc) ", Style::default().fg(Color::LightCyan)),
+        Span::raw("Cancelar"),
+    ]));
+    Paragraph::new(lines)
+        .alignment(Alignment::Center)
+        .wrap(Wrap { trim: true })
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::White))
+                .title(Span::styled(
+                    confirm.title.as_str(),
+                    Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+                ))
+                .border_type(BorderType::Rounded),
+        )
+}
+
+/// Takes the fields rather than the `Picker` so its `state` can be borrowed mutably alongside.
+pub fn render_picker<'a>(title: &'a str, options: &'a [String]) -> List<'a> {
+    let items: Vec<_> = options
+        .iter()
+        .map(|option| ListItem::new(Span::raw(option.as_str())))
+        .collect();
+    List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::White))
+                .title(title)
+                .border_type(BorderType::Rounded),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::White)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+        )
+}
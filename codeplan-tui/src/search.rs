@@ -0,0 +1,318 @@
+use tui::{
+    layout::{Constraint, Direction, Layout, Rect},
+    style::{Color, Modifier, Style},
+    text::{Span, Spans},
+    widgets::{Block, BorderType, Borders, List, ListItem, ListState, Paragraph},
+};
+
+use crate::app::{App, MenuItem};
+
+/// Results kept for display, the best ones first.
+const MAX_RESULTS: usize = 50;
+
+/// Chars kept before the first match when a long field is cut to fit a list.
+const CONTEXT_CHARS: usize = 10;
+
+/// Where Enter takes the user, by id so it survives reloads.
+///
+/// Records created offline have ids of their own too, see `models::unsynced_id`.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SearchTarget {
+    Task(usize),
+    Comment(usize),
+    Project(usize),
+}
+
+/// What `/` searches: the list of the tab it was opened on, or every tab.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SearchScope {
+    All,
+    /// The Monitor list, as filtered and sorted.
+    Tasks,
+    Comments,
+    Projects,
+}
+
+impl SearchScope {
+    pub fn of(menu_item: MenuItem) -> SearchScope {
+        match menu_item {
+            MenuItem::Monitor => SearchScope::Tasks,
+            MenuItem::Comments => SearchScope::Comments,
+            MenuItem::Projects => SearchScope::Projects,
+            _ => SearchScope::All,
+        }
+    }
+}
+
+pub struct SearchResult {
+    pub target: SearchTarget,
+    /// What the tab's list shows for the record.
+    pub title: String,
+    /// The matched field, on a single line.
+    pub text: String,
+    /// Char indices of `text` that matched the query.
+    pub positions: Vec<usize>,
+    pub score: i64,
+}
+
+/// The `/` prompt. Scoped to a tab, it filters that tab's list in place and keeps
+/// its order; otherwise it lists the best results from every tab.
+pub struct Search {
+    pub scope: SearchScope,
+    pub query: String,
+    pub results: Vec<SearchResult>,
+    pub state: ListState,
+}
+
+impl Search {
+    pub fn new(scope: SearchScope, app: &App) -> Search {
+        let mut search = Search {
+            scope,
+            query: String::new(),
+            results: Vec::new(),
+            state: ListState::default(),
+        };
+        search.update(app);
+        search
+    }
+
+    /// Searches again for a changed query, selecting the first result.
+    pub fn update(&mut self, app: &App) {
+        self.results = search(app, self.scope, &self.query);
+        self.state.select(if self.results.is_empty() { None } else { Some(0) });
+    }
+
+    /// Searches again after a reload, keeping the selected record if it still matches.
+    pub fn refresh(&mut self, app: &App) {
+        let target = self.selected().map(|result| result.target);
+        self.update(app);
+        if let Some(index) = target.and_then(|target| self.results.iter().position(|result| result.target == target)) {
+            self.state.select(Some(index));
+        }
+    }
+
+    pub fn selected(&self) -> Option<&SearchResult> {
+        self.state.selected().and_then(|selected| self.results.get(selected))
+    }
+
+    /// The records of `items` left in the filtered list, in its order.
+    pub fn filtered<T: Clone>(&self, items: &[T], target: impl Fn(&T) -> SearchTarget) -> Vec<T> {
+        self.results
+            .iter()
+            .filter_map(|result| items.iter().find(|item| target(item) == result.target))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Matches the query's chars in order, case-insensitively, returning a score
+/// (higher is better) and the positions of the matched chars.
+///
+/// Consecutive matches and matches at the start of a word score more.
+pub fn fuzzy_match(query: &str, text: &str) -> Option<(i64, Vec<usize>)> {
+    let query: Vec<char> = query.to_lowercase().chars().filter(|c| !c.is_whitespace()).collect();
+    if query.is_empty() {
+        return None;
+    }
+    let mut positions = Vec::with_capacity(query.len());
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+    let mut next = 0;
+    for (index, c) in text.chars().enumerate() {
+        if next < query.len() && c.to_lowercase().eq(query[next].to_lowercase()) {
+            score += 1;
+            if positions.last().map_or(false, |last| last + 1 == index) {
+                score += 5;
+            }
+            if previous.map_or(true, |previous| !previous.is_alphanumeric()) {
+                score += 3;
+            }
+            positions.push(index);
+            next += 1;
+        }
+        previous = Some(c);
+    }
+    if next < query.len() {
+        return None;
+    }
+    Some((score, positions))
+}
+
+fn search(app: &App, scope: SearchScope, query: &str) -> Vec<SearchResult> {
+    // A scoped search with nothing typed yet shows the whole list.
+    let keep_all = scope != SearchScope::All && query.trim().is_empty();
+    let mut results = Vec::new();
+    let tasks = match scope {
+        SearchScope::All => &app.all_tasks[..],
+        SearchScope::Tasks => &app.tasks[..],
+        _ => &[][..],
+    };
+    for task in tasks {
+        let fields = [&task.content_preview, &task.content, &task.project];
+        let target = SearchTarget::Task(task.id);
+        push_best(&mut results, query, &task.content_preview, &fields, target, keep_all);
+    }
+    if scope == SearchScope::All || scope == SearchScope::Comments {
+        for comment in &app.comments {
+            let target = SearchTarget::Comment(comment.id);
+            push_best(&mut results, query, &comment.task_preview, &[&comment.content], target, keep_all);
+        }
+    }
+    if scope == SearchScope::All || scope == SearchScope::Projects {
+        for project in &app.projects {
+            let fields = [&project.name, &project.customer_name];
+            let target = SearchTarget::Project(project.id);
+            push_best(&mut results, query, &project.name, &fields, target, keep_all);
+        }
+    }
+    if scope == SearchScope::All {
+        results.sort_by(|a, b| b.score.cmp(&a.score));
+        results.truncate(MAX_RESULTS);
+    }
+    results
+}
+
+/// Adds one result for the best matching of `fields`, if any matches or `keep_all` is set.
+fn push_best(
+    results: &mut Vec<SearchResult>,
+    query: &str,
+    title: &str,
+    fields: &[&String],
+    target: SearchTarget,
+    keep_all: bool,
+) {
+    let title = title.replace('\n', " ");
+    let best = fields
+        .iter()
+        .map(|field| field.replace('\n', " "))
+        .filter_map(|text| fuzzy_match(query, &text).map(|(score, positions)| (score, positions, text)))
+        .max_by_key(|(score, _, _)| *score);
+    let best = match best {
+        Some(best) => best,
+        None if keep_all => (0, Vec::new(), title.clone()),
+        None => return,
+    };
+    let (score, positions, text) = best;
+    results.push(SearchResult {
+        target,
+        title,
+        text,
+        positions,
+        score,
+    });
+}
+
+fn kind_label(target: SearchTarget) -> &'static str {
+    match target {
+        SearchTarget::Task(_) => "Task       ",
+        SearchTarget::Comment(_) => "Comentário ",
+        SearchTarget::Project(_) => "Projeto    ",
+    }
+}
+
+fn highlighted<'a>(result: &SearchResult) -> Spans<'a> {
+    let mut spans = vec![Span::styled(kind_label(result.target), Style::default().fg(Color::DarkGray))];
+    spans.extend(highlighted_chars(&result.text, &result.positions, Style::default()));
+    Spans::from(spans)
+}
+
+/// `text` in `style`, with the chars at `positions` picked out.
+fn highlighted_chars<'a>(text: &str, positions: &[usize], style: Style) -> Vec<Span<'a>> {
+    text.chars()
+        .enumerate()
+        .map(|(index, c)| {
+            if positions.contains(&index) {
+                Span::styled(c.to_string(), Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD))
+            } else {
+                Span::styled(c.to_string(), style)
+            }
+        })
+        .collect()
+}
+
+/// Drops the start of `text` up to shortly before its first match, so the match
+/// shows up in a narrow list.
+fn around_match(text: &str, positions: &[usize]) -> (String, Vec<usize>) {
+    let start = positions.first().map_or(0, |first| first.saturating_sub(CONTEXT_CHARS));
+    if start == 0 {
+        return (text.to_owned(), positions.to_vec());
+    }
+    let text = format!("…{}", text.chars().skip(start).collect::<String>());
+    (text, positions.iter().map(|position| position - start + 1).collect())
+}
+
+/// A row of a filtered tab list: the record's title, followed by the matched
+/// field when the match is elsewhere.
+fn filtered_line<'a>(result: &SearchResult) -> Spans<'a> {
+    if result.text == result.title {
+        return Spans::from(highlighted_chars(&result.text, &result.positions, Style::default()));
+    }
+    let (text, positions) = around_match(&result.text, &result.positions);
+    let mut spans = vec![Span::raw(result.title.clone()), Span::styled(" · ", Style::default().fg(Color::DarkGray))];
+    spans.extend(highlighted_chars(&text, &positions, Style::default().fg(Color::DarkGray)));
+    Spans::from(spans)
+}
+
+/// Splits `area` into the prompt and the result list.
+pub fn search_layout(area: Rect) -> (Rect, Rect) {
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(3), Constraint::Min(3)].as_ref())
+        .split(area);
+    (chunks[0], chunks[1])
+}
+
+/// The list of a tab filtered by a scoped search, in place of the tab's own list.
+pub fn render_search_list<'a>(title: &str, results: &[SearchResult]) -> List<'a> {
+    let items: Vec<_> = if results.is_empty() {
+        vec![ListItem::new(Span::styled("Nada encontrado", Style::default().fg(Color::DarkGray)))]
+    } else {
+        results.iter().map(|result| ListItem::new(filtered_line(result))).collect()
+    };
+    List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::White))
+                .title(format!("{} ({})", title, results.len()))
+                .border_type(BorderType::Rounded),
+        )
+        .highlight_style(
+            Style::default()
+                .bg(Color::White)
+                .fg(Color::Black)
+                .add_modifier(Modifier::BOLD),
+        )
+}
+
+pub fn render_search_prompt<'a>(query: &str) -> Paragraph<'a> {
+    Paragraph::new(Spans::from(vec![
+        Span::styled("/ ", Style::default().fg(Color::LightCyan)),
+        Span::raw(format!("{}_", query)),
+    ]))
+    .block(
+        Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White))
+            .title("Buscar — (Enter) Ir para | (Esc) Fechar")
+            .border_type(BorderType::Rounded),
+    )
+}
+
+pub fn render_search_results<'a>(query: &str, results: &[SearchResult]) -> List<'a> {
+    let items: Vec<_> = if results.is_empty() {
+        let message = if query.is_empty() { "Digite para buscar" } else { "Nada encontrado" };
+        vec![ListItem::new(Span::styled(message, Style::default().fg(Color::DarkGray)))]
+    } else {
+        results.iter().map(|result| ListItem::new(highlighted(result))).collect()
+    };
+    List::new(items)
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .style(Style::default().fg(Color::White))
+                .title(format!("Resultados ({})", results.len()))
+                .border_type(BorderType::Rounded),
+        )
+        .highlight_style(Style::default().bg(Color::DarkGray).add_modifier(Modifier::BOLD))
+}
@@ -1,59 +1,43 @@
-use reqwest::Client;
-use std::fs::File;
-use std::io::prelude::*;
+use std::env;
+
+use codeplan_core::client::Client;
+use codeplan_core::config::Settings;
+use codeplan_core::files::StoreLock;
+use codeplan_core::outbox::Outbox;
+use codeplan_core::store::SqliteStore;
+use codeplan_core::sync::{self, SyncEvent};
 
 type Error = Box<dyn std::error::Error>;
 type Result<T, E = Error> = std::result::Result<T, E>;
 
-async fn get_tasks() -> Result<()> {
-    let mut file = File::create("./cache/task.json")?;
-    let client = Client::new();
-    let req = client
-        .get("http://172.30.152.201:4000/tasks/")
-        .header("Accepts", "application/json");
-    let res = req.send().await?;
-    let body = res.bytes().await?;
-    let v = body.to_vec();
-    let s = String::from_utf8_lossy(&v);
-    let string_body = String::from(s);
-    file.write_all(string_body.as_ref())?;
-    Ok(())
-}
-
-async fn get_comments() -> Result<()> {
-    let mut file = File::create("./cache/comment.json")?;
-    let client = Client::new();
-    let req = client
-        .get("http://172.30.152.201:4000/tasks/comments/")
-        .header("Accepts", "application/json");
-    let res = req.send().await?;
-    let body = res.bytes().await?;
-    let v = body.to_vec();
-    let s = String::from_utf8_lossy(&v);
-    let string_body = String::from(s);
-    file.write_all(string_body.as_ref())?;
-    Ok(())
-}
-
-async fn get_projects() -> Result<()> {
-    let mut file = File::create("./cache/project.json")?;
-    let client = Client::new();
-    let req = client
-        .get("http://172.30.152.201:4000/projects/")
-        .header("Accepts", "application/json");
-    let res = req.send().await?;
-    let body = res.bytes().await?;
-    let v = body.to_vec();
-    let s = String::from_utf8_lossy(&v);
-    let string_body = String::from(s);
-    file.write_all(string_body.as_ref())?;
-    Ok(())
-}
-
 #[tokio::main]
 async fn main() -> Result<()> {
-    get_tasks().await?;
-    get_comments().await?;
-    get_projects().await?;
+    let args: Vec<String> = env::args().collect();
+    let settings = Settings::from_args(&args)?;
+    let client = Client::from_settings(&settings)?;
+    // Waits for a sync running in the TUI, and keeps the TUI from reloading mid-sync.
+    let _lock = StoreLock::exclusive(&settings.lock_path())?;
+    let mut store = SqliteStore::open(&settings.db_path())?;
+    if args.contains(&String::from("--full")) {
+        sync::reset_cursors(&mut store)?;
+    }
+    let mut outbox = Outbox::load(&store)?;
+    let report = sync::sync_all(&client, &mut store, &outbox.pending(), |event| match event {
+        SyncEvent::Replaying(count) => println!("sending {} queued change(s)...", count),
+        SyncEvent::Started(resource) => println!("syncing {}...", resource),
+        SyncEvent::Finished(resource, count) => println!("synced {} {}", count, resource),
+        SyncEvent::Failed(resource, e) => eprintln!("failed to sync {}: {}", resource, e),
+    })
+    .await;
+    outbox.apply_replay(&mut store, &report.replay)?;
+    for (id, e) in &report.replay.rejected {
+        eprintln!("queued change #{} rejected: {}", id, e);
+    }
+    if let Some(e) = &report.replay.interrupted {
+        eprintln!("server unreachable, queued changes kept: {}", e);
+    }
+    if !report.is_success() {
+        return Err("sync did not complete".into());
+    }
     Ok(())
-}
\ No newline at end of file
+}